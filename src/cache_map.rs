@@ -0,0 +1,285 @@
+// A string-to-string map for caching upstream responses. Entries expire after a time-to-live and
+// the least recently used ones are evicted once the map exceeds its entry count or byte budget.
+//
+// Expired entries are not dropped right away: they stay available through `get_stale` until they
+// are either refreshed with `insert` or evicted.
+//
+// A map can be backed by a persistent store, in which case every inserted value is also written
+// to the store, and the values stored by previous runs are loaded when the map is created.
+
+use crate::persistent_store::{PersistentStore, StoredEntry};
+
+#[derive(Clone, Copy, Debug)]
+pub struct CacheSettings {
+    pub max_entries: usize,
+    // Sum of key and value lengths of all entries
+    pub max_bytes: usize,
+    // `None` means entries never expire
+    pub time_to_live: Option<std::time::Duration>,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            max_entries: usize::MAX,
+            max_bytes: usize::MAX,
+            time_to_live: None,
+        }
+    }
+}
+
+struct CacheEntry {
+    value: String,
+    inserted_at: std::time::Instant,
+    // Position in the usage order, the smaller the longer ago the entry was used
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheMapState {
+    entries: std::collections::HashMap<String, CacheEntry>,
+    usage_order: std::collections::BTreeMap<u64, String>,
+    usage_counter: u64,
+    total_bytes: usize,
+}
+
+struct CachePersistence {
+    store: std::sync::Arc<PersistentStore>,
+    map_name: &'static str,
+}
+
+pub struct CacheMap {
+    settings: CacheSettings,
+    state: std::sync::Mutex<CacheMapState>,
+    persistence: Option<CachePersistence>,
+}
+
+impl CacheMap {
+    pub fn new(settings: CacheSettings) -> Self {
+        CacheMap {
+            settings,
+            state: std::sync::Mutex::new(CacheMapState::default()),
+            persistence: None,
+        }
+    }
+
+    // Creates a map backed by the persistent store and fills it with the stored entries that
+    // belong to the map
+    pub fn with_persistent_store(
+        settings: CacheSettings,
+        store: std::sync::Arc<PersistentStore>,
+        map_name: &'static str,
+        stored_entries: &[StoredEntry],
+    ) -> Self {
+        let cache_map = CacheMap {
+            persistence: Some(CachePersistence { store, map_name }),
+            ..CacheMap::new(settings)
+        };
+        let now = std::time::Instant::now();
+        for entry in stored_entries.iter().filter(|entry| entry.map == map_name) {
+            // Entries older than the process are treated as inserted at its start
+            let inserted_at = now.checked_sub(entry.age()).unwrap_or(now);
+            cache_map.insert_at(entry.key.clone(), entry.value.clone(), inserted_at);
+        }
+        cache_map
+    }
+
+    // Returns the value if it's present and hasn't expired yet
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.get_at(key, std::time::Instant::now())
+    }
+
+    // Returns the value even if it has expired
+    pub fn get_stale(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        state.touch(key)
+    }
+
+    // Inserts a new value or refreshes an existing one
+    pub fn insert<Key: Into<String>, Value: Into<String>>(&self, key: Key, value: Value) {
+        let (key, value) = (key.into(), value.into());
+        if let Some(persistence) = &self.persistence {
+            // Failing to persist shouldn't fail the request, the value is still cached in memory
+            if let Err(err) = persistence.store.append(persistence.map_name, &key, &value) {
+                eprintln!("Failed to persist cached value for \"{}\": {:?}", &key, err);
+            }
+        }
+        self.insert_at(key, value, std::time::Instant::now())
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_at(&self, key: &str, now: std::time::Instant) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        let entry = state.entries.get(key)?;
+        if self.is_expired(entry, now) {
+            return None;
+        }
+        state.touch(key)
+    }
+
+    fn insert_at(&self, key: String, value: String, now: std::time::Instant) {
+        let entry_bytes = key.len() + value.len();
+        let mut state = self.state.lock().unwrap();
+        state.remove(&key);
+        if entry_bytes > self.settings.max_bytes || self.settings.max_entries == 0 {
+            return;
+        }
+        while state.entries.len() >= self.settings.max_entries
+            || state.total_bytes + entry_bytes > self.settings.max_bytes
+        {
+            state.evict_least_recently_used();
+        }
+
+        state.usage_counter += 1;
+        let last_used = state.usage_counter;
+        state.usage_order.insert(last_used, key.clone());
+        state.total_bytes += entry_bytes;
+        state.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                inserted_at: now,
+                last_used,
+            },
+        );
+    }
+
+    fn is_expired(&self, entry: &CacheEntry, now: std::time::Instant) -> bool {
+        self.settings
+            .time_to_live
+            .map(|time_to_live| now.saturating_duration_since(entry.inserted_at) >= time_to_live)
+            .unwrap_or(false)
+    }
+}
+
+impl CacheMapState {
+    // Marks the entry as the most recently used one and returns its value
+    fn touch(&mut self, key: &str) -> Option<String> {
+        self.usage_counter += 1;
+        let usage_counter = self.usage_counter;
+        let entry = self.entries.get_mut(key)?;
+        let previous_use = std::mem::replace(&mut entry.last_used, usage_counter);
+        let value = entry.value.clone();
+        self.usage_order.remove(&previous_use);
+        self.usage_order.insert(usage_counter, key.to_string());
+        Some(value)
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.usage_order.remove(&entry.last_used);
+            self.total_bytes -= key.len() + entry.value.len();
+        }
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        if let Some((_, key)) = self.usage_order.pop_first() {
+            if let Some(entry) = self.entries.remove(&key) {
+                self.total_bytes -= key.len() + entry.value.len();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_map_time_to_live() {
+        let cache_map = CacheMap::new(CacheSettings {
+            time_to_live: Some(std::time::Duration::from_secs(60)),
+            ..CacheSettings::default()
+        });
+        let now = std::time::Instant::now();
+        cache_map.insert_at("pikachu".to_string(), "yellow".to_string(), now);
+        assert_eq!(cache_map.get_at("pikachu", now).unwrap(), "yellow");
+
+        let later = now + std::time::Duration::from_secs(61);
+        assert!(cache_map.get_at("pikachu", later).is_none());
+        assert_eq!(cache_map.get_stale("pikachu").unwrap(), "yellow");
+        assert_eq!(cache_map.len(), 1);
+
+        // Refreshing an expired entry
+        cache_map.insert_at("pikachu".to_string(), "still yellow".to_string(), later);
+        assert_eq!(cache_map.get_at("pikachu", later).unwrap(), "still yellow");
+        assert_eq!(cache_map.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_map_max_entries() {
+        let cache_map = CacheMap::new(CacheSettings {
+            max_entries: 2,
+            ..CacheSettings::default()
+        });
+        cache_map.insert("pikachu", "yellow");
+        cache_map.insert("charizard", "orange");
+        assert!(cache_map.get("pikachu").is_some());
+        cache_map.insert("bulbasaur", "green");
+
+        // charizard is the least recently used
+        assert_eq!(cache_map.len(), 2);
+        assert!(cache_map.get("charizard").is_none());
+        assert!(cache_map.get("pikachu").is_some());
+        assert!(cache_map.get("bulbasaur").is_some());
+    }
+
+    #[test]
+    fn test_cache_map_max_bytes() {
+        let cache_map = CacheMap::new(CacheSettings {
+            max_bytes: 30,
+            ..CacheSettings::default()
+        });
+        cache_map.insert("pikachu", "yellow"); // 13 bytes
+        cache_map.insert("ditto", "purple"); // 11 bytes
+        cache_map.insert("charizard", "orange"); // 15 bytes, pikachu has to go
+        assert!(cache_map.get("pikachu").is_none());
+        assert!(cache_map.get("ditto").is_some());
+        assert!(cache_map.get("charizard").is_some());
+
+        cache_map.insert("ditto", "transforms into anything at all");
+        assert!(cache_map.get("ditto").is_none());
+        assert_eq!(cache_map.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_map_with_persistent_store() {
+        let dir = crate::fixtures::make_temp_dir("cache-map-persistent-store");
+        let path = dir.join("cache.jsonl");
+        let settings = CacheSettings {
+            time_to_live: Some(std::time::Duration::from_secs(60)),
+            ..CacheSettings::default()
+        };
+
+        let (store, entries) = PersistentStore::open(&path).unwrap();
+        let store = std::sync::Arc::new(store);
+        let descriptions =
+            CacheMap::with_persistent_store(settings, store.clone(), "descriptions", &entries);
+        let translations =
+            CacheMap::with_persistent_store(settings, store.clone(), "shakespearese", &entries);
+        descriptions.insert("pikachu", "yellow");
+        translations.insert("yellow", "yellow, forsooth");
+        drop((descriptions, translations, store));
+
+        let (store, entries) = PersistentStore::open(&path).unwrap();
+        let descriptions = CacheMap::with_persistent_store(
+            settings,
+            std::sync::Arc::new(store),
+            "descriptions",
+            &entries,
+        );
+        assert_eq!(descriptions.len(), 1);
+        assert_eq!(descriptions.get("pikachu").unwrap(), "yellow");
+        assert!(descriptions.get("yellow").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+// Turning the names clients type into the names Poké API knows Pokémon by, e.g. `Mr. Mime` into
+// `mr-mime`. Poké API names are lowercase ASCII words joined with hyphens, so accents are dropped,
+// gender symbols become `-f` and `-m`, apostrophes go away and any other punctuation or whitespace
+// separates words. Letters of other scripts stay as they are for looking up localized names.
+
+pub fn canonical_name(name: &str) -> String {
+    let mut canonical = String::with_capacity(name.len());
+    for c in name.chars().flat_map(char::to_lowercase) {
+        match c {
+            '\'' | '\u{2019}' | '\u{2018}' | '\u{2032}' => {}
+            '\u{2640}' => canonical.push_str("-f"),
+            '\u{2642}' => canonical.push_str("-m"),
+            // Combining accents of decomposed letters like `e\u{301}`
+            '\u{300}'..='\u{36f}' => {}
+            c if c.is_alphanumeric() => canonical.push(fold_accent(c)),
+            _ => canonical.push('-'),
+        }
+    }
+    canonical
+        .split('-')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Latin letters with diacritics as their plain ASCII letter, other letters unchanged
+fn fold_accent(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' => 'e',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => 'i',
+        'ñ' | 'ń' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => 'o',
+        'ś' | 'š' => 's',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_name() {
+        let cases = [
+            ("charizard", "charizard"),
+            ("CharIZard", "charizard"),
+            ("  pikachu ", "pikachu"),
+            ("Mr. Mime", "mr-mime"),
+            ("mr.mime", "mr-mime"),
+            ("Mime Jr.", "mime-jr"),
+            ("Farfetch'd", "farfetchd"),
+            ("Sirfetch\u{2019}d", "sirfetchd"),
+            ("Flabébé", "flabebe"),
+            ("Flabe\u{301}be\u{301}", "flabebe"),
+            ("Nidoran♀", "nidoran-f"),
+            ("Nidoran ♂", "nidoran-m"),
+            ("Type: Null", "type-null"),
+            ("Tapu_Koko", "tapu-koko"),
+            ("Porygon-Z", "porygon-z"),
+            ("Porygon2", "porygon2"),
+            ("Ho-Oh", "ho-oh"),
+            ("Glurak", "glurak"),
+            ("リザードン", "リザードン"),
+            ("charizard,ditto", "charizard-ditto"),
+            ("?!", ""),
+        ];
+        for (name, expected) in cases.iter() {
+            assert_eq!(canonical_name(name), *expected, "name {:?}", name);
+        }
+    }
+}
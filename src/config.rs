@@ -0,0 +1,554 @@
+// Service configuration. Every setting can come from a command-line flag, an environment variable
+// or the optional TOML config file, with the following precedence (highest first):
+//   command-line flag > environment variable > config file > built-in default
+
+pub const DEFAULT_POKEAPI_URL: &str = "https://pokeapi.co/api/v2";
+pub const DEFAULT_TRANSLATOR_URL: &str = "https://api.funtranslations.com/translate";
+pub const DEFAULT_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+// What to do when Shakespeare translator responds with `TOO_MANY_REQUESTS`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RateLimitPolicy {
+    // Respond with the description in modern English
+    Untranslated,
+    // Pass the `TOO_MANY_REQUESTS` error through to the client
+    Error,
+    // Translate the description with the built-in rule-based translator
+    RuleBased,
+}
+
+// How to pick the description among the flavor texts of a Pokémon in the chosen language
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DescriptionPolicy {
+    // The first of `preferred_versions` with a flavor text, otherwise the longest flavor text
+    PreferredVersions,
+    Longest,
+    Shortest,
+    // The flavor text of the most recently released game
+    Newest,
+    Random,
+    // The longest flavor text among the games of `description_generation`
+    Generation,
+}
+
+// Which translator turns descriptions into Shakespearese
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TranslatorEngine {
+    // Shakespeare translator API
+    FunTranslations,
+    // The built-in rule-based translator, which works offline and has no request quota
+    RuleBased,
+}
+
+// Whether upstream exchanges are recorded into the fixture directory or replayed from it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FixtureMode {
+    Off,
+    Record,
+    Replay,
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub bind_address: std::net::IpAddr,
+    pub port: u16,
+    pub pokeapi_url: String,
+    pub translator_url: String,
+    pub translator_engine: TranslatorEngine,
+    pub cache_capacity: usize,
+    pub cache_max_bytes: usize,
+    // In seconds, 0 means entries never expire
+    pub description_cache_ttl: u64,
+    pub translation_cache_ttl: u64,
+    // Where cached responses are kept between restarts, `None` to keep them in memory only
+    pub cache_path: Option<std::path::PathBuf>,
+    pub rate_limit_policy: RateLimitPolicy,
+    // Shakespeare translator requests allowed per hour and per day, 0 means no limit
+    pub translator_hourly_quota: u32,
+    pub translator_daily_quota: u32,
+    // Descriptions left untranslated because of the quota wait in a queue of this size to be
+    // translated in the background, retried every `translation_retry_interval` seconds
+    pub translation_queue_capacity: usize,
+    pub translation_retry_interval: u64,
+    // Whether error responses include the internal error description, which may mention upstream
+    // urls and other details not meant for the public
+    pub expose_error_details: bool,
+    // Languages of descriptions to fall back to when none of the languages asked for by the client
+    // has a description, most preferred first
+    pub description_languages: Vec<String>,
+    // Descriptions in these languages get translated into Shakespearese, the rest are served as is
+    pub translated_languages: Vec<String>,
+    pub description_policy: DescriptionPolicy,
+    // Game versions as named by Poké API, most preferred first
+    pub preferred_versions: Vec<String>,
+    pub description_generation: u32,
+    // How long a single request to each upstream may take, in milliseconds
+    pub pokeapi_timeout_ms: u64,
+    pub translator_timeout_ms: u64,
+    // Failed upstream requests are retried this many times, after a random delay of up to
+    // `upstream_retry_base_delay_ms` doubled with every retry
+    pub upstream_max_retries: u32,
+    pub upstream_retry_base_delay_ms: u64,
+    // After this many failures in a row, requests to the upstream fail fast for
+    // `circuit_breaker_open_duration` seconds. 0 disables the circuit breaker.
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_open_duration: u64,
+    // Settings of the HTTP client shared by all upstream requests. Durations are in seconds,
+    // 0 disables closing idle connections and TCP keep-alive respectively.
+    pub http_pool_max_idle_per_host: usize,
+    pub http_pool_idle_timeout: u64,
+    pub http_tcp_keepalive: u64,
+    pub http_user_agent: String,
+    // Proxy for all upstream requests, `None` to use the system proxy settings
+    pub http_proxy: Option<String>,
+    // An extra trusted root certificate in PEM format, e.g. of a corporate proxy
+    pub http_ca_certificate: Option<std::path::PathBuf>,
+    // Most Pokémon a single batch request may name, and how many of them are looked up at once
+    pub batch_max_size: usize,
+    pub batch_concurrency: usize,
+    // Whether to fetch the names of all species in all languages at startup, so that Pokémon can
+    // be looked up by their localized names. Takes a request per species on every startup, so it's
+    // off by default.
+    pub localized_names: bool,
+    pub fixture_mode: FixtureMode,
+    pub fixture_dir: std::path::PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bind_address: std::net::IpAddr::from([0, 0, 0, 0]),
+            port: 5000,
+            pokeapi_url: DEFAULT_POKEAPI_URL.to_string(),
+            translator_url: DEFAULT_TRANSLATOR_URL.to_string(),
+            translator_engine: TranslatorEngine::FunTranslations,
+            cache_capacity: 1200,
+            cache_max_bytes: 16 * 1024 * 1024,
+            description_cache_ttl: 24 * 60 * 60,
+            translation_cache_ttl: 0,
+            cache_path: None,
+            rate_limit_policy: RateLimitPolicy::Untranslated,
+            translator_hourly_quota: 5,
+            translator_daily_quota: 60,
+            translation_queue_capacity: 100,
+            translation_retry_interval: 60,
+            expose_error_details: false,
+            description_languages: vec!["en".to_string()],
+            translated_languages: vec!["en".to_string()],
+            description_policy: DescriptionPolicy::PreferredVersions,
+            preferred_versions: vec!["ruby".to_string()],
+            description_generation: 3,
+            pokeapi_timeout_ms: 5000,
+            translator_timeout_ms: 5000,
+            upstream_max_retries: 2,
+            upstream_retry_base_delay_ms: 100,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_open_duration: 30,
+            http_pool_max_idle_per_host: 16,
+            http_pool_idle_timeout: 90,
+            http_tcp_keepalive: 60,
+            http_user_agent: DEFAULT_USER_AGENT.to_string(),
+            http_proxy: None,
+            http_ca_certificate: None,
+            batch_max_size: 20,
+            batch_concurrency: 4,
+            localized_names: false,
+            fixture_mode: FixtureMode::Off,
+            fixture_dir: std::path::PathBuf::from("fixtures"),
+        }
+    }
+}
+
+impl Config {
+    pub fn socket_address(&self) -> std::net::SocketAddr {
+        std::net::SocketAddr::new(self.bind_address, self.port)
+    }
+
+    // Reads the configuration from the process command line, environment and the config file.
+    // Invalid command-line flags terminate the process with a usage message.
+    pub fn from_env() -> std::result::Result<Config, String> {
+        use clap::Parser;
+        Config::resolve(CommandLineArgs::parse())
+    }
+
+    fn resolve(args: CommandLineArgs) -> std::result::Result<Config, String> {
+        let file_settings = match &args.config {
+            Some(path) => ConfigLayer::from_file(path)?,
+            None => ConfigLayer::default(),
+        };
+        Config::default().apply(file_settings)?.apply(args.settings)
+    }
+
+    fn apply(self, layer: ConfigLayer) -> std::result::Result<Config, String> {
+        let config = Config {
+            bind_address: layer.bind_address.unwrap_or(self.bind_address),
+            port: layer.port.unwrap_or(self.port),
+            pokeapi_url: layer
+                .pokeapi_url
+                .map(|url| validate_base_url("PokeAPI", url))
+                .transpose()?
+                .unwrap_or(self.pokeapi_url),
+            translator_url: layer
+                .translator_url
+                .map(|url| validate_base_url("translator", url))
+                .transpose()?
+                .unwrap_or(self.translator_url),
+            translator_engine: layer.translator_engine.unwrap_or(self.translator_engine),
+            cache_capacity: layer.cache_capacity.unwrap_or(self.cache_capacity),
+            cache_max_bytes: layer.cache_max_bytes.unwrap_or(self.cache_max_bytes),
+            description_cache_ttl: layer
+                .description_cache_ttl
+                .unwrap_or(self.description_cache_ttl),
+            translation_cache_ttl: layer
+                .translation_cache_ttl
+                .unwrap_or(self.translation_cache_ttl),
+            cache_path: layer.cache_path.or(self.cache_path),
+            rate_limit_policy: layer.rate_limit_policy.unwrap_or(self.rate_limit_policy),
+            translator_hourly_quota: layer
+                .translator_hourly_quota
+                .unwrap_or(self.translator_hourly_quota),
+            translator_daily_quota: layer
+                .translator_daily_quota
+                .unwrap_or(self.translator_daily_quota),
+            translation_queue_capacity: layer
+                .translation_queue_capacity
+                .unwrap_or(self.translation_queue_capacity),
+            translation_retry_interval: layer
+                .translation_retry_interval
+                .unwrap_or(self.translation_retry_interval),
+            expose_error_details: layer
+                .expose_error_details
+                .unwrap_or(self.expose_error_details),
+            description_languages: layer
+                .description_languages
+                .unwrap_or(self.description_languages),
+            translated_languages: layer
+                .translated_languages
+                .unwrap_or(self.translated_languages),
+            description_policy: layer.description_policy.unwrap_or(self.description_policy),
+            preferred_versions: layer.preferred_versions.unwrap_or(self.preferred_versions),
+            description_generation: layer
+                .description_generation
+                .unwrap_or(self.description_generation),
+            pokeapi_timeout_ms: layer.pokeapi_timeout_ms.unwrap_or(self.pokeapi_timeout_ms),
+            translator_timeout_ms: layer
+                .translator_timeout_ms
+                .unwrap_or(self.translator_timeout_ms),
+            upstream_max_retries: layer
+                .upstream_max_retries
+                .unwrap_or(self.upstream_max_retries),
+            upstream_retry_base_delay_ms: layer
+                .upstream_retry_base_delay_ms
+                .unwrap_or(self.upstream_retry_base_delay_ms),
+            circuit_breaker_threshold: layer
+                .circuit_breaker_threshold
+                .unwrap_or(self.circuit_breaker_threshold),
+            circuit_breaker_open_duration: layer
+                .circuit_breaker_open_duration
+                .unwrap_or(self.circuit_breaker_open_duration),
+            http_pool_max_idle_per_host: layer
+                .http_pool_max_idle_per_host
+                .unwrap_or(self.http_pool_max_idle_per_host),
+            http_pool_idle_timeout: layer
+                .http_pool_idle_timeout
+                .unwrap_or(self.http_pool_idle_timeout),
+            http_tcp_keepalive: layer.http_tcp_keepalive.unwrap_or(self.http_tcp_keepalive),
+            http_user_agent: layer.http_user_agent.unwrap_or(self.http_user_agent),
+            http_proxy: layer.http_proxy.or(self.http_proxy),
+            http_ca_certificate: layer.http_ca_certificate.or(self.http_ca_certificate),
+            batch_max_size: layer.batch_max_size.unwrap_or(self.batch_max_size),
+            batch_concurrency: layer.batch_concurrency.unwrap_or(self.batch_concurrency),
+            localized_names: layer.localized_names.unwrap_or(self.localized_names),
+            fixture_mode: layer.fixture_mode.unwrap_or(self.fixture_mode),
+            fixture_dir: layer.fixture_dir.unwrap_or(self.fixture_dir),
+        };
+        Ok(config)
+    }
+}
+
+// Base URLs are joined with paths by plain concatenation, so we keep them without trailing slash
+fn validate_base_url(name: &str, url: String) -> std::result::Result<String, String> {
+    reqwest::Url::parse(&url)
+        .map_err(|err| format!("Invalid {} base url \"{}\": {}", name, url, err))?;
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+#[derive(clap::Parser, Debug)]
+#[command(about = "A web service describing Pokémon in Shakespearese")]
+struct CommandLineArgs {
+    /// Path to a TOML file with any of the settings below, e.g. `port = 5001`
+    #[arg(long, env = "POKEMON_CONFIG")]
+    config: Option<std::path::PathBuf>,
+
+    #[command(flatten)]
+    settings: ConfigLayer,
+}
+
+// A partially specified configuration, as it comes from a single source
+#[derive(clap::Args, serde::Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigLayer {
+    /// Address to listen on
+    #[arg(long, env = "POKEMON_BIND_ADDRESS")]
+    bind_address: Option<std::net::IpAddr>,
+
+    /// Port to listen on
+    #[arg(long, env = "POKEMON_PORT")]
+    port: Option<u16>,
+
+    /// Base url of Poké API
+    #[arg(long, env = "POKEMON_POKEAPI_URL")]
+    pokeapi_url: Option<String>,
+
+    /// Base url of Shakespeare translator API
+    #[arg(long, env = "POKEMON_TRANSLATOR_URL")]
+    translator_url: Option<String>,
+
+    /// Which translator to use
+    #[arg(long, env = "POKEMON_TRANSLATOR_ENGINE", value_enum)]
+    translator_engine: Option<TranslatorEngine>,
+
+    /// Maximum number of entries in each response cache
+    #[arg(long, env = "POKEMON_CACHE_CAPACITY")]
+    cache_capacity: Option<usize>,
+
+    /// Maximum total size of keys and values in each response cache, in bytes
+    #[arg(long, env = "POKEMON_CACHE_MAX_BYTES")]
+    cache_max_bytes: Option<usize>,
+
+    /// How long Pokémon descriptions are cached for, in seconds, 0 to never expire
+    #[arg(long, env = "POKEMON_DESCRIPTION_CACHE_TTL")]
+    description_cache_ttl: Option<u64>,
+
+    /// How long Shakespeare translations are cached for, in seconds, 0 to never expire
+    #[arg(long, env = "POKEMON_TRANSLATION_CACHE_TTL")]
+    translation_cache_ttl: Option<u64>,
+
+    /// File to keep cached responses in between restarts
+    #[arg(long, env = "POKEMON_CACHE_PATH")]
+    cache_path: Option<std::path::PathBuf>,
+
+    /// What to respond with when the translator rate limit is exceeded
+    #[arg(long, env = "POKEMON_RATE_LIMIT_POLICY", value_enum)]
+    rate_limit_policy: Option<RateLimitPolicy>,
+
+    /// Shakespeare translator requests allowed per hour, 0 for no limit
+    #[arg(long, env = "POKEMON_TRANSLATOR_HOURLY_QUOTA")]
+    translator_hourly_quota: Option<u32>,
+
+    /// Shakespeare translator requests allowed per day, 0 for no limit
+    #[arg(long, env = "POKEMON_TRANSLATOR_DAILY_QUOTA")]
+    translator_daily_quota: Option<u32>,
+
+    /// How many untranslated descriptions to keep for translating in the background, 0 to disable
+    #[arg(long, env = "POKEMON_TRANSLATION_QUEUE_CAPACITY")]
+    translation_queue_capacity: Option<usize>,
+
+    /// How often to retry translating the queued descriptions, in seconds
+    #[arg(long, env = "POKEMON_TRANSLATION_RETRY_INTERVAL")]
+    translation_retry_interval: Option<u64>,
+
+    /// Include internal error details in error responses, for debugging
+    #[arg(long, env = "POKEMON_EXPOSE_ERROR_DETAILS")]
+    expose_error_details: Option<bool>,
+
+    /// Comma-separated languages of descriptions to fall back to, most preferred first
+    #[arg(long, env = "POKEMON_DESCRIPTION_LANGUAGES", value_delimiter = ',')]
+    description_languages: Option<Vec<String>>,
+
+    /// Comma-separated languages of descriptions to translate into Shakespearese
+    #[arg(long, env = "POKEMON_TRANSLATED_LANGUAGES", value_delimiter = ',')]
+    translated_languages: Option<Vec<String>>,
+
+    /// How to pick the description among the flavor texts of a Pokémon
+    #[arg(long, env = "POKEMON_DESCRIPTION_POLICY", value_enum)]
+    description_policy: Option<DescriptionPolicy>,
+
+    /// Comma-separated game versions to take descriptions from, most preferred first
+    #[arg(long, env = "POKEMON_PREFERRED_VERSIONS", value_delimiter = ',')]
+    preferred_versions: Option<Vec<String>>,
+
+    /// Game generation to take descriptions from with the `generation` policy
+    #[arg(long, env = "POKEMON_DESCRIPTION_GENERATION")]
+    description_generation: Option<u32>,
+
+    /// Timeout of a single Poké API request, in milliseconds
+    #[arg(long, env = "POKEMON_POKEAPI_TIMEOUT_MS")]
+    pokeapi_timeout_ms: Option<u64>,
+
+    /// Timeout of a single Shakespeare translator request, in milliseconds
+    #[arg(long, env = "POKEMON_TRANSLATOR_TIMEOUT_MS")]
+    translator_timeout_ms: Option<u64>,
+
+    /// How many times to retry upstream requests failing with timeouts, connection errors or 5xx
+    #[arg(long, env = "POKEMON_UPSTREAM_MAX_RETRIES")]
+    upstream_max_retries: Option<u32>,
+
+    /// Maximum delay before the first retry, doubled for every next one, in milliseconds
+    #[arg(long, env = "POKEMON_UPSTREAM_RETRY_BASE_DELAY_MS")]
+    upstream_retry_base_delay_ms: Option<u64>,
+
+    /// Failures in a row after which requests to an upstream fail fast, 0 to never fail fast
+    #[arg(long, env = "POKEMON_CIRCUIT_BREAKER_THRESHOLD")]
+    circuit_breaker_threshold: Option<u32>,
+
+    /// How long requests to a failing upstream fail fast for, in seconds
+    #[arg(long, env = "POKEMON_CIRCUIT_BREAKER_OPEN_DURATION")]
+    circuit_breaker_open_duration: Option<u64>,
+
+    /// Maximum number of idle connections kept open to each upstream host
+    #[arg(long, env = "POKEMON_HTTP_POOL_MAX_IDLE_PER_HOST")]
+    http_pool_max_idle_per_host: Option<usize>,
+
+    /// How long idle upstream connections are kept open, in seconds, 0 to keep them forever
+    #[arg(long, env = "POKEMON_HTTP_POOL_IDLE_TIMEOUT")]
+    http_pool_idle_timeout: Option<u64>,
+
+    /// Interval of TCP keep-alive probes on upstream connections, in seconds, 0 to disable
+    #[arg(long, env = "POKEMON_HTTP_TCP_KEEPALIVE")]
+    http_tcp_keepalive: Option<u64>,
+
+    /// User-Agent header of upstream requests
+    #[arg(long, env = "POKEMON_HTTP_USER_AGENT")]
+    http_user_agent: Option<String>,
+
+    /// Proxy url for all upstream requests, e.g. `http://proxy.example.com:3128`
+    #[arg(long, env = "POKEMON_HTTP_PROXY")]
+    http_proxy: Option<String>,
+
+    /// PEM file with an extra trusted root certificate, e.g. of a corporate proxy
+    #[arg(long, env = "POKEMON_HTTP_CA_CERTIFICATE")]
+    http_ca_certificate: Option<std::path::PathBuf>,
+
+    /// Most Pokémon a single batch request may name
+    #[arg(long, env = "POKEMON_BATCH_MAX_SIZE")]
+    batch_max_size: Option<usize>,
+
+    /// How many Pokémon of a batch request are looked up concurrently
+    #[arg(long, env = "POKEMON_BATCH_CONCURRENCY")]
+    batch_concurrency: Option<usize>,
+
+    /// Load the names of all Pokémon in all languages at startup to look Pokémon up by them
+    #[arg(long, env = "POKEMON_LOCALIZED_NAMES")]
+    localized_names: Option<bool>,
+
+    /// Record upstream exchanges into the fixture directory or replay them from it
+    #[arg(long, env = "POKEMON_FIXTURE_MODE", value_enum)]
+    fixture_mode: Option<FixtureMode>,
+
+    /// Directory with recorded upstream exchanges
+    #[arg(long, env = "POKEMON_FIXTURE_DIR")]
+    fixture_dir: Option<std::path::PathBuf>,
+}
+
+impl ConfigLayer {
+    fn from_file(path: &std::path::Path) -> std::result::Result<ConfigLayer, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read config file {}: {}", path.display(), err))?;
+        ConfigLayer::from_toml(&content)
+            .map_err(|err| format!("Failed to parse config file {}: {}", path.display(), err))
+    }
+
+    fn from_toml(content: &str) -> std::result::Result<ConfigLayer, toml::de::Error> {
+        toml::from_str(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> CommandLineArgs {
+        use clap::Parser;
+        CommandLineArgs::try_parse_from(
+            std::iter::once("pokemon-in-shakespeare").chain(args.iter().cloned()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+        assert_eq!(config.socket_address(), ([0, 0, 0, 0], 5000).into());
+        assert_eq!(config.pokeapi_url, "https://pokeapi.co/api/v2");
+        assert_eq!(config.rate_limit_policy, RateLimitPolicy::Untranslated);
+    }
+
+    #[test]
+    fn test_command_line_args() {
+        let config = Config::resolve(parse_args(&[
+            "--bind-address",
+            "127.0.0.1",
+            "--port",
+            "5001",
+            "--pokeapi-url",
+            "http://localhost:8000/api/v2/",
+            "--rate-limit-policy",
+            "error",
+            "--expose-error-details",
+            "true",
+            "--description-languages",
+            "de,en",
+        ]))
+        .unwrap();
+        assert_eq!(config.socket_address(), ([127, 0, 0, 1], 5001).into());
+        assert_eq!(config.pokeapi_url, "http://localhost:8000/api/v2");
+        assert_eq!(config.translator_url, DEFAULT_TRANSLATOR_URL);
+        assert_eq!(config.rate_limit_policy, RateLimitPolicy::Error);
+        assert!(config.expose_error_details);
+        assert_eq!(config.description_languages, ["de", "en"]);
+        assert_eq!(config.translated_languages, ["en"]);
+
+        assert!(Config::resolve(parse_args(&["--translator-url", "banana"])).is_err());
+    }
+
+    #[test]
+    fn test_config_file_precedence() {
+        let file_settings = ConfigLayer::from_toml(
+            r#"
+            port = 6000
+            cache-capacity = 10
+            description-cache-ttl = 3600
+            rate-limit-policy = "error"
+            translator-engine = "rule-based"
+            translator-hourly-quota = 10
+            translated-languages = ["en", "de"]
+            description-policy = "generation"
+            description-generation = 4
+            batch-max-size = 6
+            localized-names = true
+            "#,
+        )
+        .unwrap();
+        let args = parse_args(&["--port", "7000"]);
+        let config = Config::default()
+            .apply(file_settings)
+            .and_then(|config| config.apply(args.settings))
+            .unwrap();
+        assert_eq!(config.port, 7000);
+        assert_eq!(config.cache_capacity, 10);
+        assert_eq!(config.description_cache_ttl, 3600);
+        assert_eq!(config.translation_cache_ttl, 0);
+        assert_eq!(config.rate_limit_policy, RateLimitPolicy::Error);
+        assert_eq!(config.translator_engine, TranslatorEngine::RuleBased);
+        assert_eq!(config.translator_hourly_quota, 10);
+        assert_eq!(config.translator_daily_quota, 60);
+        assert_eq!(config.translated_languages, ["en", "de"]);
+        assert_eq!(config.description_policy, DescriptionPolicy::Generation);
+        assert_eq!(config.description_generation, 4);
+        assert_eq!(config.preferred_versions, ["ruby"]);
+        assert_eq!(config.batch_max_size, 6);
+        assert_eq!(config.batch_concurrency, 4);
+        assert!(config.localized_names);
+
+        assert!(ConfigLayer::from_toml("banana = 1").is_err());
+        assert!(ConfigLayer::from_toml("port = \"five thousand\"").is_err());
+    }
+}
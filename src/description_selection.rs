@@ -0,0 +1,370 @@
+// Picking one of the flavor texts of a Pokémon. A client may ask for the flavor text of a specific
+// game version, otherwise the configured `DescriptionSelector` picks one among the flavor texts in
+// the chosen language.
+
+use crate::config::{Config, DescriptionPolicy};
+use crate::pokeapi::PokeApiPokemonDescription;
+
+// What the client asked for, languages most preferred first
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescriptionPreferences {
+    pub languages: Vec<String>,
+    pub version: Option<String>,
+}
+
+impl DescriptionPreferences {
+    pub fn new(languages: Vec<String>) -> Self {
+        DescriptionPreferences {
+            languages,
+            version: None,
+        }
+    }
+
+    pub fn with_version(self, version: Option<String>) -> Self {
+        DescriptionPreferences {
+            version: version.map(|version| version.trim().to_lowercase()),
+            ..self
+        }
+    }
+}
+
+// A strategy of picking the flavor text among the ones in a single language
+pub trait DescriptionSelector: Send + Sync {
+    fn select<'d>(
+        &self,
+        candidates: &[&'d PokeApiPokemonDescription],
+    ) -> Option<&'d PokeApiPokemonDescription>;
+
+    // The policy with its parameters, e.g. `generation:3`, since descriptions picked by another
+    // policy may still be cached from before a configuration change
+    fn cache_key(&self) -> String;
+
+    // Whether the same candidates always get the same pick, so that the pick can be cached
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+}
+
+pub fn make_description_selector(config: &Config) -> Box<dyn DescriptionSelector> {
+    match config.description_policy {
+        DescriptionPolicy::PreferredVersions => Box::new(PreferredVersions {
+            versions: config.preferred_versions.clone(),
+        }),
+        DescriptionPolicy::Longest => Box::new(Longest),
+        DescriptionPolicy::Shortest => Box::new(Shortest),
+        DescriptionPolicy::Newest => Box::new(Newest),
+        DescriptionPolicy::Random => Box::new(RandomDescription),
+        DescriptionPolicy::Generation => Box::new(Generation {
+            generation: config.description_generation,
+        }),
+    }
+}
+
+// The flavor text of the requested version, or none if that version has none, otherwise whatever
+// the selector picks
+pub fn select_description<'d>(
+    candidates: &[&'d PokeApiPokemonDescription],
+    requested_version: Option<&str>,
+    selector: &dyn DescriptionSelector,
+) -> Option<&'d PokeApiPokemonDescription> {
+    match requested_version {
+        Some(version) => candidates
+            .iter()
+            .find(|entry| entry.version.name == version)
+            .copied(),
+        None => selector.select(candidates),
+    }
+}
+
+// The first of the versions that has a flavor text, otherwise the longest flavor text
+pub struct PreferredVersions {
+    pub versions: Vec<String>,
+}
+
+impl DescriptionSelector for PreferredVersions {
+    fn cache_key(&self) -> String {
+        format!("preferred-versions:{}", self.versions.join(","))
+    }
+
+    fn select<'d>(
+        &self,
+        candidates: &[&'d PokeApiPokemonDescription],
+    ) -> Option<&'d PokeApiPokemonDescription> {
+        self.versions
+            .iter()
+            .find_map(|version| {
+                candidates
+                    .iter()
+                    .find(|entry| entry.version.name == *version)
+            })
+            .copied()
+            .or_else(|| Longest.select(candidates))
+    }
+}
+
+pub struct Longest;
+
+impl DescriptionSelector for Longest {
+    fn cache_key(&self) -> String {
+        "longest".to_string()
+    }
+
+    fn select<'d>(
+        &self,
+        candidates: &[&'d PokeApiPokemonDescription],
+    ) -> Option<&'d PokeApiPokemonDescription> {
+        candidates
+            .iter()
+            .max_by_key(|entry| entry.flavor_text.chars().count())
+            .copied()
+    }
+}
+
+pub struct Shortest;
+
+impl DescriptionSelector for Shortest {
+    fn cache_key(&self) -> String {
+        "shortest".to_string()
+    }
+
+    fn select<'d>(
+        &self,
+        candidates: &[&'d PokeApiPokemonDescription],
+    ) -> Option<&'d PokeApiPokemonDescription> {
+        candidates
+            .iter()
+            .min_by_key(|entry| entry.flavor_text.chars().count())
+            .copied()
+    }
+}
+
+// The flavor text of the most recently released game, versions we don't know count as the oldest
+pub struct Newest;
+
+impl DescriptionSelector for Newest {
+    fn cache_key(&self) -> String {
+        "newest".to_string()
+    }
+
+    fn select<'d>(
+        &self,
+        candidates: &[&'d PokeApiPokemonDescription],
+    ) -> Option<&'d PokeApiPokemonDescription> {
+        candidates
+            .iter()
+            .max_by_key(|entry| release_order(&entry.version.name))
+            .copied()
+    }
+}
+
+pub struct RandomDescription;
+
+impl DescriptionSelector for RandomDescription {
+    fn cache_key(&self) -> String {
+        "random".to_string()
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn select<'d>(
+        &self,
+        candidates: &[&'d PokeApiPokemonDescription],
+    ) -> Option<&'d PokeApiPokemonDescription> {
+        use rand::seq::SliceRandom;
+        candidates.choose(&mut rand::thread_rng()).copied()
+    }
+}
+
+// The longest flavor text among the games of the generation, otherwise the longest of all
+pub struct Generation {
+    pub generation: u32,
+}
+
+impl DescriptionSelector for Generation {
+    fn cache_key(&self) -> String {
+        format!("generation:{}", self.generation)
+    }
+
+    fn select<'d>(
+        &self,
+        candidates: &[&'d PokeApiPokemonDescription],
+    ) -> Option<&'d PokeApiPokemonDescription> {
+        let of_generation = candidates
+            .iter()
+            .filter(|entry| version_generation(&entry.version.name) == Some(self.generation))
+            .copied()
+            .collect::<Vec<_>>();
+        Longest
+            .select(&of_generation)
+            .or_else(|| Longest.select(candidates))
+    }
+}
+
+// Poké API game versions with flavor texts in the order of release, with their generation
+const VERSIONS: &[(&str, u32)] = &[
+    ("red", 1),
+    ("blue", 1),
+    ("yellow", 1),
+    ("gold", 2),
+    ("silver", 2),
+    ("crystal", 2),
+    ("ruby", 3),
+    ("sapphire", 3),
+    ("colosseum", 3),
+    ("firered", 3),
+    ("leafgreen", 3),
+    ("emerald", 3),
+    ("xd", 3),
+    ("diamond", 4),
+    ("pearl", 4),
+    ("platinum", 4),
+    ("heartgold", 4),
+    ("soulsilver", 4),
+    ("black", 5),
+    ("white", 5),
+    ("black-2", 5),
+    ("white-2", 5),
+    ("x", 6),
+    ("y", 6),
+    ("omega-ruby", 6),
+    ("alpha-sapphire", 6),
+    ("sun", 7),
+    ("moon", 7),
+    ("ultra-sun", 7),
+    ("ultra-moon", 7),
+    ("lets-go-pikachu", 7),
+    ("lets-go-eevee", 7),
+    ("sword", 8),
+    ("shield", 8),
+    ("brilliant-diamond", 8),
+    ("shining-pearl", 8),
+    ("legends-arceus", 8),
+    ("scarlet", 9),
+    ("violet", 9),
+];
+
+fn release_order(version: &str) -> Option<usize> {
+    VERSIONS.iter().position(|(name, _)| *name == version)
+}
+
+fn version_generation(version: &str) -> Option<u32> {
+    VERSIONS
+        .iter()
+        .find(|(name, _)| *name == version)
+        .map(|(_, generation)| *generation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pokeapi::PokemonDescriptionResponse;
+
+    fn charizard_species() -> PokemonDescriptionResponse {
+        serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/pokeapi/pokemon-species/6.json"
+        )))
+        .unwrap()
+    }
+
+    fn english_candidates(species: &PokemonDescriptionResponse) -> Vec<&PokeApiPokemonDescription> {
+        species
+            .descriptions
+            .iter()
+            .filter(|entry| entry.language.name == "en")
+            .collect()
+    }
+
+    #[test]
+    fn test_description_selectors() {
+        let species = charizard_species();
+        let candidates = english_candidates(&species);
+        let selected_version = |selector: &dyn DescriptionSelector| {
+            selector
+                .select(&candidates)
+                .map(|entry| entry.version.name.clone())
+        };
+
+        let selectors: Vec<(Box<dyn DescriptionSelector>, &str)> = vec![
+            (
+                Box::new(PreferredVersions {
+                    versions: vec!["sword".to_string(), "emerald".to_string()],
+                }),
+                "emerald",
+            ),
+            (
+                Box::new(PreferredVersions {
+                    versions: vec!["sword".to_string()],
+                }),
+                "ruby",
+            ),
+            (Box::new(Longest), "ruby"),
+            (Box::new(Shortest), "red"),
+            (Box::new(Newest), "emerald"),
+            (Box::new(Generation { generation: 1 }), "yellow"),
+            (Box::new(Generation { generation: 8 }), "ruby"),
+        ];
+        for (selector, expected_version) in selectors.iter() {
+            assert_eq!(
+                selected_version(selector.as_ref()).as_deref(),
+                Some(*expected_version)
+            );
+        }
+
+        let random_version = selected_version(&RandomDescription).unwrap();
+        assert!(candidates
+            .iter()
+            .any(|entry| entry.version.name == random_version));
+        assert!(RandomDescription.select(&[]).is_none());
+    }
+
+    #[test]
+    fn test_selector_cache_keys() {
+        let cache_key = |description_policy, description_generation| {
+            make_description_selector(&Config {
+                description_policy,
+                description_generation,
+                ..Config::default()
+            })
+            .cache_key()
+        };
+        assert_ne!(
+            cache_key(DescriptionPolicy::Longest, 3),
+            cache_key(DescriptionPolicy::Shortest, 3)
+        );
+        assert_ne!(
+            cache_key(DescriptionPolicy::Generation, 3),
+            cache_key(DescriptionPolicy::Generation, 4)
+        );
+        // The generation only matters to its own policy
+        assert_eq!(
+            cache_key(DescriptionPolicy::Longest, 3),
+            cache_key(DescriptionPolicy::Longest, 4)
+        );
+        assert_ne!(
+            PreferredVersions {
+                versions: vec!["ruby".to_string()]
+            }
+            .cache_key(),
+            PreferredVersions {
+                versions: vec!["emerald".to_string()]
+            }
+            .cache_key()
+        );
+    }
+
+    #[test]
+    fn test_select_requested_version() {
+        let species = charizard_species();
+        let candidates = english_candidates(&species);
+        let selected_version = |requested_version| {
+            select_description(&candidates, requested_version, &Shortest)
+                .map(|entry| entry.version.name.clone())
+        };
+        assert_eq!(selected_version(Some("yellow")).as_deref(), Some("yellow"));
+        assert_eq!(selected_version(Some("scarlet")), None);
+        assert_eq!(selected_version(None).as_deref(), Some("red"));
+    }
+}
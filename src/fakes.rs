@@ -0,0 +1,194 @@
+// In-memory stand-ins for the upstream services, so the request path can be tested offline
+
+use crate::config::TranslatorEngine;
+use crate::name_index::PokemonName;
+use crate::pokeapi::{
+    PokeApiPokemonDescription, PokeApiPokemonDescriptionLanguage, PokeApiPokemonDescriptionVersion,
+    PokeApiSpeciesName, PokemonDescriptionResponse, PokemonSource,
+};
+use crate::translator::Translator;
+use crate::{RequestError, RequestErrorKind, Result};
+
+#[derive(Default)]
+pub struct FakePokemonSource {
+    species: std::collections::HashMap<String, PokemonDescriptionResponse>,
+    // Pokédex numbers and localized names of the species, by pokemon name
+    species_names: std::collections::HashMap<String, (u32, Vec<PokeApiSpeciesName>)>,
+    request_count: std::sync::atomic::AtomicUsize,
+    latency: std::time::Duration,
+    // How many of the next requests for names time out
+    name_failures: std::sync::atomic::AtomicUsize,
+}
+
+impl FakePokemonSource {
+    // Adds a pokemon with a list of (version, language, flavor text) descriptions
+    pub fn with_pokemon(mut self, pokemon_name: &str, descriptions: &[(&str, &str, &str)]) -> Self {
+        let descriptions = descriptions
+            .iter()
+            .map(|(version, language, text)| PokeApiPokemonDescription {
+                version: PokeApiPokemonDescriptionVersion {
+                    name: version.to_string(),
+                },
+                flavor_text: text.to_string(),
+                language: PokeApiPokemonDescriptionLanguage {
+                    name: language.to_string(),
+                },
+            })
+            .collect();
+        self.species.insert(
+            pokemon_name.to_string(),
+            PokemonDescriptionResponse { descriptions },
+        );
+        self
+    }
+
+    // Gives a pokemon added with `with_pokemon` a Pokédex number and a list of (language, name)
+    // localized names
+    pub fn with_species_names(
+        mut self,
+        pokemon_name: &str,
+        pokedex_number: u32,
+        names: &[(&str, &str)],
+    ) -> Self {
+        let names = names
+            .iter()
+            .map(|(language, name)| PokeApiSpeciesName {
+                name: name.to_string(),
+                language: PokeApiPokemonDescriptionLanguage {
+                    name: language.to_string(),
+                },
+            })
+            .collect();
+        self.species_names
+            .insert(pokemon_name.to_string(), (pokedex_number, names));
+        self
+    }
+
+    // Makes every lookup take a while, like a real network request would
+    pub fn with_latency(self, latency: std::time::Duration) -> Self {
+        FakePokemonSource { latency, ..self }
+    }
+
+    // Makes the first `failures` requests for the list of names or the localized names of a species
+    // time out, like a flaky Poké API
+    pub fn with_name_failures(self, failures: usize) -> Self {
+        FakePokemonSource {
+            name_failures: std::sync::atomic::AtomicUsize::new(failures),
+            ..self
+        }
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.request_count.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn fail_names_request(&self) -> Result<()> {
+        let failing = self.name_failures.fetch_update(
+            std::sync::atomic::Ordering::SeqCst,
+            std::sync::atomic::Ordering::SeqCst,
+            |failures| failures.checked_sub(1),
+        );
+        match failing {
+            Ok(_) => Err(RequestError::new(
+                RequestErrorKind::UpstreamTimeout,
+                "Timed out loading names",
+            )),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PokemonSource for FakePokemonSource {
+    async fn pokemon_species(&self, pokemon_name: &str) -> Result<PokemonDescriptionResponse> {
+        self.request_count
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+        self.species.get(pokemon_name).cloned().ok_or_else(|| {
+            RequestError::new(
+                RequestErrorKind::NotFound,
+                format!("Failed to find a pokemon {}", pokemon_name),
+            )
+        })
+    }
+
+    async fn pokemon_names(&self) -> Result<Vec<PokemonName>> {
+        self.fail_names_request()?;
+        let mut names = self.species.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        Ok(names
+            .into_iter()
+            .map(|name| PokemonName {
+                number: self.species_names.get(&name).map(|(number, _)| *number),
+                name,
+            })
+            .collect())
+    }
+
+    async fn species_names(&self, pokedex_number: u32) -> Result<Vec<PokeApiSpeciesName>> {
+        self.fail_names_request()?;
+        self.species_names
+            .values()
+            .find(|(number, _)| *number == pokedex_number)
+            .map(|(_, names)| names.clone())
+            .ok_or_else(|| {
+                RequestError::new(
+                    RequestErrorKind::NotFound,
+                    format!("Failed to find a species {}", pokedex_number),
+                )
+            })
+    }
+}
+
+// Prepends "Verily, " to the input and starts responding with `TOO_MANY_REQUESTS` after the quota
+// of requests is used up
+pub struct FakeTranslator {
+    quota: std::sync::atomic::AtomicUsize,
+    request_count: std::sync::atomic::AtomicUsize,
+}
+
+impl FakeTranslator {
+    pub fn new() -> Self {
+        FakeTranslator::with_quota(usize::MAX)
+    }
+
+    pub fn with_quota(quota: usize) -> Self {
+        FakeTranslator {
+            quota: std::sync::atomic::AtomicUsize::new(quota),
+            request_count: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.request_count.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    // Raises the quota by `extra_requests`, like the upstream quota window moving on
+    pub fn increase_quota(&self, extra_requests: usize) {
+        self.quota
+            .fetch_add(extra_requests, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[async_trait::async_trait]
+impl Translator for FakeTranslator {
+    async fn shakespearise(&self, input: &str) -> Result<String> {
+        let previous_requests = self
+            .request_count
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if previous_requests >= self.quota.load(std::sync::atomic::Ordering::SeqCst) {
+            return Err(RequestError::new(
+                RequestErrorKind::RateLimited,
+                "Failed to query Shakespeare API",
+            ));
+        }
+        Ok(format!("Verily, {}", input))
+    }
+
+    // Stands in for Shakespeare translator API
+    fn engine(&self) -> TranslatorEngine {
+        TranslatorEngine::FunTranslations
+    }
+}
@@ -0,0 +1,109 @@
+// Storage for recorded upstream exchanges. Every exchange is kept in its own JSON file named after
+// the request url, so recordings are easy to inspect, edit and commit.
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct RecordedExchange {
+    pub url: String,
+    pub status: u16,
+    pub body: String,
+}
+
+pub struct FixtureStore {
+    dir: std::path::PathBuf,
+}
+
+impl FixtureStore {
+    pub fn new<P: Into<std::path::PathBuf>>(dir: P) -> Self {
+        FixtureStore { dir: dir.into() }
+    }
+
+    pub fn load(&self, url: &str) -> Option<RecordedExchange> {
+        let content = std::fs::read_to_string(self.fixture_path(url)).ok()?;
+        serde_json::from_str::<RecordedExchange>(&content)
+            .ok()
+            .filter(|exchange| exchange.url == url)
+    }
+
+    pub fn save(&self, exchange: &RecordedExchange) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_string_pretty(exchange)?;
+        std::fs::write(self.fixture_path(&exchange.url), content + "\n")
+    }
+
+    // File names are a readable prefix of the url followed by its hash, e.g.
+    // `pokeapi_co_api_v2_pokemon_charizard-3f5c0c3bd1b67ea5.json`
+    fn fixture_path(&self, url: &str) -> std::path::PathBuf {
+        const MAX_PREFIX_LENGTH: usize = 80;
+        let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+        let prefix = without_scheme
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .take(MAX_PREFIX_LENGTH)
+            .collect::<String>();
+        let file_name = format!(
+            "{}-{:016x}.json",
+            prefix.trim_matches('_'),
+            fnv1a_hash(url.as_bytes())
+        );
+        self.dir.join(file_name)
+    }
+}
+
+// Unlike `std::collections::hash_map::DefaultHasher`, FNV-1a is guaranteed to stay the same
+// between Rust releases, so fixture names and the Pokémon of the day stay stable
+pub fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+pub fn make_temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "pokemon-in-shakespeare-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_store() {
+        let dir = make_temp_dir("fixture-store");
+        let store = FixtureStore::new(&dir);
+        let exchange = RecordedExchange {
+            url: "https://pokeapi.co/api/v2/pokemon/charizard".to_string(),
+            status: 200,
+            body: "{}".to_string(),
+        };
+        assert!(store.load(&exchange.url).is_none());
+        store.save(&exchange).unwrap();
+        assert_eq!(store.load(&exchange.url), Some(exchange));
+        assert!(store
+            .load("https://pokeapi.co/api/v2/pokemon/charizard/")
+            .is_none());
+
+        let file_names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(file_names.len(), 1);
+        assert!(file_names[0].starts_with("pokeapi_co_api_v2_pokemon_charizard-"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fnv1a_hash() {
+        assert_eq!(fnv1a_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(
+            fnv1a_hash(b"https://pokeapi.co/api/v2/pokemon/klink"),
+            fnv1a_hash(b"https://pokeapi.co/api/v2/pokemon/klink/")
+        );
+    }
+}
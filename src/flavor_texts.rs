@@ -0,0 +1,94 @@
+// All flavor texts of a Pokémon, for clients showing every description rather than the one we pick.
+// Games often reuse a flavor text, so each distinct text in a language is listed once together with
+// all the game versions it appears in.
+
+use crate::normalization::normalize_flavor_text;
+use crate::pokeapi::PokemonDescriptionResponse;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct FlavorText {
+    pub text: String,
+    pub language: String,
+    // In the order Poké API lists them
+    pub versions: Vec<String>,
+}
+
+// Flavor texts grouped by language in the order the languages first appear, and within a language
+// in the order the texts first appear
+pub fn group_flavor_texts(species: &PokemonDescriptionResponse) -> Vec<FlavorText> {
+    let mut flavor_texts: Vec<FlavorText> = Vec::new();
+    for entry in species.descriptions.iter() {
+        let text = normalize_flavor_text(&entry.flavor_text);
+        let existing = flavor_texts.iter_mut().find(|flavor_text| {
+            flavor_text.language == entry.language.name && flavor_text.text == text
+        });
+        match existing {
+            Some(flavor_text) => {
+                if !flavor_text.versions.contains(&entry.version.name) {
+                    flavor_text.versions.push(entry.version.name.clone());
+                }
+            }
+            None => flavor_texts.push(FlavorText {
+                text,
+                language: entry.language.name.clone(),
+                versions: vec![entry.version.name.clone()],
+            }),
+        }
+    }
+    // A stable sort keeps the order of texts within a language
+    let language_order = flavor_texts
+        .iter()
+        .fold(Vec::new(), |mut languages, flavor_text| {
+            if !languages.contains(&flavor_text.language) {
+                languages.push(flavor_text.language.clone());
+            }
+            languages
+        });
+    flavor_texts.sort_by_key(|flavor_text| {
+        language_order
+            .iter()
+            .position(|language| *language == flavor_text.language)
+    });
+    flavor_texts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_flavor_texts() {
+        let species: PokemonDescriptionResponse = serde_json::from_value(serde_json::json!({
+            "flavor_text_entries": [
+                {"flavor_text": "Spits fire.", "language": {"name": "en"}, "version": {"name": "red"}},
+                {"flavor_text": "Crache du feu.", "language": {"name": "fr"}, "version": {"name": "x"}},
+                {"flavor_text": "Spits fire.", "language": {"name": "en"}, "version": {"name": "blue"}},
+                {"flavor_text": "Spits\nfire.", "language": {"name": "en"}, "version": {"name": "yellow"}},
+                {"flavor_text": "Flies around.", "language": {"name": "en"}, "version": {"name": "ruby"}},
+                {"flavor_text": "Spits fire.", "language": {"name": "en"}, "version": {"name": "red"}}
+            ]
+        }))
+        .unwrap();
+        let flavor_texts = group_flavor_texts(&species);
+        assert_eq!(
+            flavor_texts,
+            vec![
+                FlavorText {
+                    text: "Spits fire.".to_string(),
+                    language: "en".to_string(),
+                    versions: vec!["red".to_string(), "blue".to_string(), "yellow".to_string()],
+                },
+                FlavorText {
+                    text: "Flies around.".to_string(),
+                    language: "en".to_string(),
+                    versions: vec!["ruby".to_string()],
+                },
+                FlavorText {
+                    text: "Crache du feu.".to_string(),
+                    language: "fr".to_string(),
+                    versions: vec!["x".to_string()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+// All upstream HTTP traffic goes through `HttpClient`, which can record every exchange into a
+// fixture directory or replay the recorded exchanges without touching the network. It holds the
+// single `reqwest::Client` of the service, so connections to the upstream services are pooled and
+// reused across requests instead of connecting anew for every request.
+
+use crate::config::{Config, FixtureMode};
+use crate::fixtures::{FixtureStore, RecordedExchange};
+use crate::{RequestError, Result};
+
+#[derive(Debug)]
+pub struct UpstreamResponse {
+    pub status: http::StatusCode,
+    pub url: String,
+    pub body: String,
+}
+
+#[derive(Default)]
+enum Fixtures {
+    #[default]
+    Off,
+    Record(FixtureStore),
+    Replay(FixtureStore),
+}
+
+pub struct HttpClient {
+    client: reqwest::Client,
+    fixtures: Fixtures,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        HttpClient {
+            client: reqwest::Client::new(),
+            fixtures: Fixtures::Off,
+        }
+    }
+}
+
+impl HttpClient {
+    pub fn new(config: &Config) -> std::result::Result<Self, String> {
+        let fixtures = match config.fixture_mode {
+            FixtureMode::Off => Fixtures::Off,
+            FixtureMode::Record => Fixtures::Record(FixtureStore::new(&config.fixture_dir)),
+            FixtureMode::Replay => Fixtures::Replay(FixtureStore::new(&config.fixture_dir)),
+        };
+        Ok(HttpClient {
+            client: build_reqwest_client(config)?,
+            fixtures,
+        })
+    }
+
+    pub async fn get(&self, url: &str) -> Result<UpstreamResponse> {
+        if let Fixtures::Replay(store) = &self.fixtures {
+            return store.load(url).map(UpstreamResponse::from).ok_or_else(|| {
+                RequestError::new_internal(format!("No recorded response for url {}", url))
+            });
+        }
+
+        let response = self.client.get(url).send().await?;
+        let response = UpstreamResponse {
+            status: response.status(),
+            url: url.to_string(),
+            body: response.text().await?,
+        };
+        if let Fixtures::Record(store) = &self.fixtures {
+            // Failing to record shouldn't fail the request
+            if let Err(err) = store.save(&RecordedExchange::from(&response)) {
+                eprintln!("Failed to record response for url {}: {:?}", url, err);
+            }
+        }
+        Ok(response)
+    }
+}
+
+fn build_reqwest_client(config: &Config) -> std::result::Result<reqwest::Client, String> {
+    let seconds_or_none =
+        |seconds| Some(std::time::Duration::from_secs(seconds)).filter(|_| seconds > 0);
+    let mut builder = reqwest::Client::builder()
+        .user_agent(&config.http_user_agent)
+        .pool_max_idle_per_host(config.http_pool_max_idle_per_host)
+        .pool_idle_timeout(seconds_or_none(config.http_pool_idle_timeout))
+        .tcp_keepalive(seconds_or_none(config.http_tcp_keepalive));
+    if let Some(proxy_url) = &config.http_proxy {
+        let proxy = reqwest::Proxy::all(proxy_url)
+            .map_err(|err| format!("Invalid proxy url \"{}\": {}", proxy_url, err))?;
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &config.http_ca_certificate {
+        let pem = std::fs::read(path)
+            .map_err(|err| format!("Failed to read CA certificate {}: {}", path.display(), err))?;
+        let certificate = reqwest::Certificate::from_pem(&pem)
+            .map_err(|err| format!("Failed to parse CA certificate {}: {}", path.display(), err))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+    builder
+        .build()
+        .map_err(|err| format!("Failed to create HTTP client: {}", err))
+}
+
+impl From<RecordedExchange> for UpstreamResponse {
+    fn from(exchange: RecordedExchange) -> Self {
+        UpstreamResponse {
+            status: http::StatusCode::from_u16(exchange.status)
+                .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR),
+            url: exchange.url,
+            body: exchange.body,
+        }
+    }
+}
+
+impl From<&UpstreamResponse> for RecordedExchange {
+    fn from(response: &UpstreamResponse) -> Self {
+        RecordedExchange {
+            url: response.url.clone(),
+            status: response.status.as_u16(),
+            body: response.body.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_client_settings() {
+        assert!(HttpClient::new(&Config::default()).is_ok());
+
+        let config = Config {
+            http_proxy: Some("not a url".to_string()),
+            ..Config::default()
+        };
+        assert!(HttpClient::new(&config).is_err());
+
+        let config = Config {
+            http_ca_certificate: Some(std::path::PathBuf::from("/nonexistent/ca.pem")),
+            ..Config::default()
+        };
+        let err = HttpClient::new(&config).err().unwrap();
+        assert!(err.contains("ca.pem"));
+    }
+
+    // Compares the latency of a client created for every request, as `reqwest::get` does, with
+    // the shared client, against a local plain HTTP server. Responses are only checked after the
+    // timing. Run with `cargo test --release -- --ignored --nocapture bench`.
+    #[tokio::test]
+    #[ignore]
+    async fn bench_shared_http_client() {
+        use warp::Filter;
+        const REQUEST_COUNT: u32 = 500;
+
+        let route = warp::any().map(|| "{}");
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let url = format!("http://{}/pokemon/pikachu", address);
+
+        let mut fresh_client_responses = Vec::new();
+        let started = std::time::Instant::now();
+        for _ in 0..REQUEST_COUNT {
+            fresh_client_responses.push(async { reqwest::get(&url).await?.text().await }.await);
+        }
+        let fresh_client_latency = started.elapsed() / REQUEST_COUNT;
+
+        let http_client = HttpClient::new(&Config::default()).unwrap();
+        let mut shared_client_responses = Vec::new();
+        let started = std::time::Instant::now();
+        for _ in 0..REQUEST_COUNT {
+            shared_client_responses.push(http_client.get(&url).await);
+        }
+        let shared_client_latency = started.elapsed() / REQUEST_COUNT;
+
+        assert!(fresh_client_responses
+            .iter()
+            .all(|response| response.is_ok()));
+        assert!(shared_client_responses
+            .iter()
+            .all(|response| response.is_ok()));
+
+        println!(
+            "Average latency: {:?} with a client per request, {:?} with the shared client",
+            fresh_client_latency, shared_client_latency
+        );
+        assert!(shared_client_latency < fresh_client_latency);
+    }
+}
@@ -0,0 +1,112 @@
+// Languages of Pokémon descriptions. Clients ask for languages with the `lang` query parameter and
+// the `Accept-Language` header, in this order of priority, and the configured languages are the
+// fallback when none of the requested ones has a description. Languages are named the way Poké API
+// names them (`en`, `de`, `ja-Hrkt`, `zh-Hans`, ...), compared ignoring case.
+
+// Keeps the cache keys short no matter what the clients send
+const MAX_REQUESTED_LANGUAGES: usize = 8;
+
+// The languages to look for a description in, most preferred first, lowercase and without repeats
+pub fn preferred_languages(
+    lang_param: Option<&str>,
+    accept_language: Option<&str>,
+    fallback_languages: &[String],
+) -> Vec<String> {
+    let requested = lang_param
+        .map(|languages| languages.split(',').map(str::to_string).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .chain(
+            accept_language
+                .map(parse_accept_language)
+                .unwrap_or_default(),
+        )
+        .filter(|language| is_valid_language_tag(language))
+        .take(MAX_REQUESTED_LANGUAGES);
+    let mut languages = Vec::new();
+    for language in requested.chain(fallback_languages.iter().cloned()) {
+        let language = language.trim().to_lowercase();
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    languages
+}
+
+// Language tags of an `Accept-Language` header ordered by their quality, e.g. `de-CH, fr;q=0.5`.
+// The wildcard and tags with zero quality are dropped.
+fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                .next()
+                .map(|quality| quality.trim().parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            Some((tag.to_string(), quality)).filter(|_| tag != "*" && quality > 0.0)
+        })
+        .collect::<Vec<_>>();
+    // The sort is stable, so tags of equal quality keep their order
+    weighted.sort_by(|(_, left), (_, right)| right.total_cmp(left));
+    weighted.into_iter().map(|(tag, _)| tag).collect()
+}
+
+fn is_valid_language_tag(tag: &str) -> bool {
+    let tag = tag.trim();
+    !tag.is_empty() && tag.len() <= 35 && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+// Whether a description in `available` language satisfies the `requested` one. A language matches
+// its own regional variants and the other way round, e.g. `de` and `de-AT` or `zh` and `zh-Hans`.
+pub fn matches_language(requested: &str, available: &str) -> bool {
+    let primary_subtag = |tag: &str| tag.split('-').next().unwrap_or_default().to_lowercase();
+    requested.eq_ignore_ascii_case(available)
+        || (!requested.contains('-') || !available.contains('-'))
+            && primary_subtag(requested) == primary_subtag(available)
+}
+
+pub fn is_one_of(language: &str, languages: &[String]) -> bool {
+    languages
+        .iter()
+        .any(|candidate| candidate.eq_ignore_ascii_case(language))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferred_languages() {
+        let english = vec!["en".to_string()];
+        assert_eq!(preferred_languages(None, None, &english), ["en"]);
+        assert_eq!(
+            preferred_languages(Some("de"), Some("fr-CH, fr;q=0.9, *;q=0.5"), &english),
+            ["de", "fr-ch", "fr", "en"]
+        );
+        assert_eq!(
+            preferred_languages(None, Some("ja;q=0.2, ko, EN;q=0.8, de;q=0"), &english),
+            ["ko", "en", "ja"]
+        );
+        assert_eq!(
+            preferred_languages(Some("it,ES"), None, &["fr".to_string(), "en".to_string()]),
+            ["it", "es", "fr", "en"]
+        );
+        assert_eq!(
+            preferred_languages(Some("<script>,"), Some(";;;,q=1"), &english),
+            ["en"]
+        );
+    }
+
+    #[test]
+    fn test_matches_language() {
+        assert!(matches_language("de", "de"));
+        assert!(matches_language("pt-br", "pt-BR"));
+        assert!(matches_language("de-at", "de"));
+        assert!(matches_language("zh", "zh-Hans"));
+        assert!(!matches_language("zh-hant", "zh-Hans"));
+        assert!(!matches_language("ja", "ko"));
+    }
+}
@@ -1,259 +1,741 @@
+extern crate async_trait;
 extern crate bytes;
-extern crate chashmap;
+extern crate clap;
 extern crate futures;
 extern crate reqwest;
 extern crate serde;
 extern crate serde_json;
 extern crate tokio;
+extern crate toml;
 extern crate url;
 extern crate warp;
 
+mod cache_map;
+mod canonical_name;
+mod config;
+mod description_selection;
+#[cfg(test)]
+mod fakes;
+mod fixtures;
+mod flavor_texts;
+mod http_client;
+mod language;
+#[cfg(test)]
+mod mock_server;
+mod name_index;
+mod normalization;
+mod persistent_store;
+mod pokeapi;
+mod pokemon_of_the_day;
+mod problem;
+mod request_error;
+mod rule_based_translator;
+mod single_flight;
+mod translation_queue;
+mod translator;
+mod translator_quota;
+mod upstream_client;
+
+use cache_map::{CacheMap, CacheSettings};
+use canonical_name::canonical_name;
+use config::{Config, RateLimitPolicy, TranslatorEngine};
+use description_selection::{DescriptionPreferences, DescriptionSelector};
+use flavor_texts::FlavorText;
+use http_client::HttpClient;
+use name_index::{LocalizedName, NameIndex, PokemonName};
+use persistent_store::PersistentStore;
+use pokeapi::{PokeApi, PokemonDescriptionResponse, PokemonSource};
+use problem::ProblemDetails;
+use request_error::{RequestError, RequestErrorKind, Upstream};
+use rule_based_translator::RuleBasedTranslator;
+use single_flight::SingleFlight;
+use translation_queue::TranslationQueue;
+use translator::{FunTranslationsApi, Translator};
+use translator_quota::{QuotaLimitedTranslator, TranslatorQuota};
+use upstream_client::{UpstreamClient, UpstreamSettings};
+
 type Result<T> = std::result::Result<T, RequestError>;
 
 #[tokio::main]
 async fn main() {
+    let config = match Config::from_env() {
+        Ok(config) => std::sync::Arc::new(config),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
     println!();
     println!("Pokémons in Shakespearese");
     println!();
     println!("  Query format: /pokemon/<pokemon name>");
-    println!("  For example, try `curl http://<server address>:5000/pokemon/charizard`");
+    println!(
+        "  For example, try `curl http://<server address>:{}/pokemon/charizard`",
+        config.port
+    );
 
-    let cache = std::sync::Arc::new(ResponseCache::new());
-    warp::serve(pokemon_name_filter(cache.clone()))
-        .run(([0, 0, 0, 0], 5000))
-        .await;
+    let http_client = match HttpClient::new(&config) {
+        Ok(http_client) => std::sync::Arc::new(http_client),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    let translator: Box<dyn Translator> = match config.translator_engine {
+        TranslatorEngine::FunTranslations => Box::new(QuotaLimitedTranslator::new(
+            FunTranslationsApi::new(
+                &config.translator_url,
+                UpstreamClient::new(
+                    http_client.clone(),
+                    UpstreamSettings::for_translator(&config),
+                ),
+            ),
+            TranslatorQuota::new(&config),
+        )),
+        TranslatorEngine::RuleBased => Box::new(RuleBasedTranslator),
+    };
+    let cache = std::sync::Arc::new(ResponseCache::new(
+        &config,
+        PokeApi::new(
+            &config.pokeapi_url,
+            UpstreamClient::new(http_client, UpstreamSettings::for_pokeapi(&config)),
+        ),
+        translator,
+    ));
+    tokio::spawn({
+        let cache = cache.clone();
+        let localized_names = config.localized_names;
+        async move {
+            if localized_names {
+                cache
+                    .load_localized_names(LOCALIZED_NAMES_RETRY_INTERVAL)
+                    .await;
+            } else if let Err(err) = cache.name_index().await {
+                eprintln!(
+                    "Failed to load pokemon names, will retry on demand: {:?}",
+                    err
+                );
+            }
+        }
+    });
+    tokio::spawn(run_daily_pokemon_warmup(cache.clone(), config.clone()));
+    tokio::spawn(run_translation_worker(
+        cache.clone(),
+        std::time::Duration::from_secs(config.translation_retry_interval.max(1)),
+    ));
+    let socket_address = config.socket_address();
+    warp::serve(routes(cache, config)).run(socket_address).await;
+}
+
+fn routes<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Filter;
+    pokemon_suggest_filter(cache.clone(), config.clone())
+        .or(pokemon_pick_filter(cache.clone(), config.clone()))
+        .or(pokemon_name_filter(cache.clone(), config.clone()))
+        .or(pokemon_descriptions_filter(cache.clone(), config.clone()))
+        .or(pokemon_batch_filter(cache.clone(), config.clone()))
+        .or(translator_quota_filter(cache))
 }
 
-fn pokemon_name_filter(
-    cache: std::sync::Arc<ResponseCache>,
+// Paths under `/pokemon/` with routes of their own, which are never looked up as names even when
+// their own route rejects the request
+const RESERVED_POKEMON_PATHS: [&str; 3] = ["suggest", "random", "daily"];
+
+fn pokemon_name_filter<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
 ) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     use warp::Filter;
+    let pokemon_name = warp::path::param::<String>().and_then(|param: String| async move {
+        if RESERVED_POKEMON_PATHS.contains(&param.as_str()) {
+            Err(warp::reject::not_found())
+        } else {
+            Ok(param)
+        }
+    });
     warp::path("pokemon")
-        .and(warp::path::param::<String>())
+        .and(pokemon_name)
         .and(warp::path::end())
         .and(warp::get())
-        .and_then(move |param: String| {
-            let cache = cache.clone();
-            async move { respond_with_pokemon_in_shakespearese(cache, param).await }
-        })
+        .and(warp::query::<PokemonQuery>())
+        .and(warp::header::optional::<String>("accept-language"))
+        .and_then(
+            move |param: String, query: PokemonQuery, accept_language: Option<String>| {
+                let cache = cache.clone();
+                let config = config.clone();
+                let preferences = query.preferences(accept_language.as_deref(), &config);
+                let fuzzy = query.fuzzy.unwrap_or(false);
+                let pokemon_name = decode_path_segment(&param);
+                async move {
+                    respond_with_pokemon_in_shakespearese(
+                        cache,
+                        config,
+                        pokemon_name,
+                        preferences,
+                        fuzzy,
+                    )
+                    .await
+                }
+            },
+        )
 }
 
-#[derive(Debug)]
-struct RequestError {
-    status: http::StatusCode,
-    description: String,
+// Warp leaves path parameters percent-encoded, e.g. `Mr.%20Mime`
+fn decode_path_segment(segment: &str) -> String {
+    percent_encoding::percent_decode_str(segment)
+        .decode_utf8_lossy()
+        .into_owned()
 }
 
-impl RequestError {
-    fn new<S: std::convert::Into<String>>(
-        status: http::StatusCode,
-        description: S,
-    ) -> RequestError {
-        RequestError {
-            status,
-            description: description.into(),
-        }
-    }
+#[derive(serde::Deserialize)]
+struct PokemonQuery {
+    // Comma-separated languages of the description, most preferred first
+    lang: Option<String>,
+    // The game version to take the description from, if it has one
+    version: Option<String>,
+    // Whether to describe the Pokémon with the closest name when there is none with this name
+    fuzzy: Option<bool>,
+}
 
-    fn new_internal<S: std::convert::Into<String>>(description: S) -> RequestError {
-        RequestError::new(http::StatusCode::INTERNAL_SERVER_ERROR, description.into())
+impl PokemonQuery {
+    fn preferences(
+        &self,
+        accept_language: Option<&str>,
+        config: &Config,
+    ) -> DescriptionPreferences {
+        DescriptionPreferences::new(language::preferred_languages(
+            self.lang.as_deref(),
+            accept_language,
+            &config.description_languages,
+        ))
+        .with_version(self.version.clone())
     }
 }
 
-#[derive(serde::Deserialize)]
-struct PokeApiPokemonSpeciesInfo {
-    url: String,
+// Looks up many Pokémon at once, either `POST /pokemon/batch` with a JSON body like
+// `{"names": ["charizard", "ditto"]}` or `GET /pokemon?names=charizard,ditto`. Both take the same
+// `lang`, `version` and `fuzzy` query parameters as a single lookup.
+fn pokemon_batch_filter<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Filter;
+    let post = warp::path!("pokemon" / "batch")
+        .and(warp::post())
+        .and(warp::query::<PokemonQuery>())
+        .and(warp::body::content_length_limit(BATCH_BODY_LIMIT))
+        .and(warp::body::json())
+        .map(|query: PokemonQuery, body: BatchRequest| (body.names, query));
+    let get = warp::path("pokemon")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query::<BatchQuery>())
+        .map(|query: BatchQuery| {
+            let names = query.names.split(',').map(str::to_string).collect();
+            let query = PokemonQuery {
+                lang: query.lang,
+                version: query.version,
+                fuzzy: query.fuzzy,
+            };
+            (names, query)
+        });
+    post.or(get)
+        .unify()
+        .and(warp::header::optional::<String>("accept-language"))
+        .and_then(
+            move |(names, query): (Vec<String>, PokemonQuery), accept_language: Option<String>| {
+                let cache = cache.clone();
+                let config = config.clone();
+                let preferences = query.preferences(accept_language.as_deref(), &config);
+                let fuzzy = query.fuzzy.unwrap_or(false);
+                async move { respond_with_batch(cache, config, names, preferences, fuzzy).await }
+            },
+        )
 }
 
+// Way more than any batch of names within `batch_max_size` needs
+const BATCH_BODY_LIMIT: u64 = 64 * 1024;
+
 #[derive(serde::Deserialize)]
-struct PokemonResponse {
-    species: PokeApiPokemonSpeciesInfo,
+struct BatchRequest {
+    names: Vec<String>,
 }
 
 #[derive(serde::Deserialize)]
-struct PokeApiPokemonDescriptionLanguage {
-    name: String,
+struct BatchQuery {
+    // Comma-separated names of the Pokémon
+    names: String,
+    lang: Option<String>,
+    version: Option<String>,
+    fuzzy: Option<bool>,
 }
 
-#[derive(serde::Deserialize)]
-struct PokeApiPokemonDescriptionVersion {
-    name: String,
+fn pokemon_descriptions_filter<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Filter;
+    warp::path!("pokemon" / String / "descriptions")
+        .and(warp::get())
+        .and(warp::query::<DescriptionsQuery>())
+        .and_then(move |param: String, query: DescriptionsQuery| {
+            let cache = cache.clone();
+            let config = config.clone();
+            let pokemon_name = decode_path_segment(&param);
+            async move { respond_with_all_descriptions(cache, config, pokemon_name, query).await }
+        })
 }
 
 #[derive(serde::Deserialize)]
-struct PokeApiPokemonDescription {
-    version: PokeApiPokemonDescriptionVersion,
-    flavor_text: String,
-    language: PokeApiPokemonDescriptionLanguage,
+struct DescriptionsQuery {
+    // Comma-separated languages to list the descriptions in, all languages when missing
+    lang: Option<String>,
+    // Whether to translate the descriptions into Shakespearese, as far as the quota allows
+    translate: Option<bool>,
+}
+
+fn pokemon_suggest_filter<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Filter;
+    warp::path!("pokemon" / "suggest")
+        .and(warp::get())
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and_then(move |params: std::collections::HashMap<String, String>| {
+            let cache = cache.clone();
+            let config = config.clone();
+            async move {
+                match SuggestQuery::parse(&params) {
+                    Ok(query) => respond_with_name_suggestions(cache, config, query).await,
+                    Err(err) => Ok(problem_response(&err, "/pokemon/suggest", &config)),
+                }
+            }
+        })
+}
+
+const DEFAULT_SUGGEST_LIMIT: usize = 10;
+const MAX_SUGGEST_LIMIT: usize = 50;
+
+struct SuggestQuery {
+    // What the client typed so far
+    q: String,
+    // How many names to suggest at most, up to `MAX_SUGGEST_LIMIT`
+    limit: Option<usize>,
+}
+
+impl SuggestQuery {
+    // Parsed by hand rather than by `warp::query`, whose rejection would let the request fall
+    // through to other routes
+    fn parse(params: &std::collections::HashMap<String, String>) -> Result<Self> {
+        let invalid = |description: String| {
+            RequestError::new(RequestErrorKind::InvalidSuggestQuery, description)
+        };
+        let q = params
+            .get("q")
+            .ok_or_else(|| invalid("Missing the q parameter".to_string()))?;
+        let limit = params
+            .get("limit")
+            .map(|limit| {
+                limit
+                    .parse::<usize>()
+                    .map_err(|_| invalid(format!("The limit {:?} isn't a number", limit)))
+            })
+            .transpose()?;
+        Ok(SuggestQuery {
+            q: q.clone(),
+            limit,
+        })
+    }
+}
+
+// `/pokemon/random` and `/pokemon/daily`, described like any other Pokémon
+fn pokemon_pick_filter<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Filter;
+    let random = warp::path!("pokemon" / "random")
+        .and(warp::query::<PokemonQuery>())
+        .map(|query: PokemonQuery| (PokemonPick::Random, query));
+    let daily = warp::path!("pokemon" / "daily")
+        .and(warp::query::<DailyQuery>())
+        .map(|query: DailyQuery| {
+            let pick = PokemonPick::Daily { seed: query.seed };
+            let query = PokemonQuery {
+                lang: query.lang,
+                version: query.version,
+                fuzzy: None,
+            };
+            (pick, query)
+        });
+    random
+        .or(daily)
+        .unify()
+        .and(warp::get())
+        .and(warp::header::optional::<String>("accept-language"))
+        .and_then(
+            move |(pick, query): (PokemonPick, PokemonQuery), accept_language: Option<String>| {
+                let cache = cache.clone();
+                let config = config.clone();
+                let preferences = query.preferences(accept_language.as_deref(), &config);
+                async move { respond_with_picked_pokemon(cache, config, pick, preferences).await }
+            },
+        )
 }
 
 #[derive(serde::Deserialize)]
-struct PokemonDescriptionResponse {
-    #[serde(rename(deserialize = "flavor_text_entries"))]
-    descriptions: Vec<PokeApiPokemonDescription>,
+struct DailyQuery {
+    // Clients with different seeds get different Pokémon of the day
+    seed: Option<String>,
+    lang: Option<String>,
+    version: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PokemonPick {
+    Random,
+    // The same Pokémon for the whole UTC day
+    Daily { seed: Option<String> },
+}
+
+impl PokemonPick {
+    // Only Pokémon with a Pokédex number take part, alternative forms would make some Pokémon come
+    // up more often than others
+    // `day` as counted by `pokemon_of_the_day::utc_day`, only the daily pick depends on it
+    fn pick(&self, name_index: &NameIndex, day: u64) -> Option<String> {
+        let names = name_index
+            .numbered_names()
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        let position = match self {
+            PokemonPick::Random => {
+                use rand::Rng;
+                Some(rand::thread_rng().gen_range(0..names.len().max(1)))
+            }
+            PokemonPick::Daily { seed } => {
+                pokemon_of_the_day::daily_position(day, seed.as_deref(), names.len())
+            }
+        };
+        position
+            .and_then(|position| names.get(position))
+            .map(|name| name.to_string())
+    }
+
+    fn path(&self) -> &'static str {
+        match self {
+            PokemonPick::Random => "/pokemon/random",
+            PokemonPick::Daily { .. } => "/pokemon/daily",
+        }
+    }
+}
+
+fn translator_quota_filter<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Filter;
+    warp::path!("translator" / "quota")
+        .and(warp::get())
+        .map(move || {
+            warp::reply::json(&TranslatorQuotaResponse {
+                remaining: cache.translator.remaining_quota(),
+            })
+        })
 }
 
+// Bumped whenever fields of `PokemonInShakespeareseResponse` change. Version 1 had only `name` and
+// `description`, version 2 didn't have `corrected_from` and version 3 didn't have `localized_name`.
+const RESPONSE_VERSION: u32 = 4;
+
 #[derive(serde::Serialize, serde::Deserialize)]
 struct PokemonInShakespeareseResponse {
+    response_version: u32,
     name: String,
     description: String,
+    // False when the description is in modern English because the translator quota is used up
+    translated: bool,
+    translator_engine: Option<TranslatorEngine>,
+    // The game the flavor text comes from and its language, as named by Poké API
+    game_version: String,
+    language: String,
+    // True when neither Poké API nor the translator had to be asked
+    served_from_cache: bool,
+    // The requested name when there was no such Pokémon and the closest name was used instead
+    corrected_from: Option<String>,
+    // The name the Pokémon was requested by when it's a name in another language
+    localized_name: Option<RequestedLocalizedName>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct RequestedLocalizedName {
+    name: String,
+    language: String,
 }
 
 impl PokemonInShakespeareseResponse {
-    fn new<Name: Into<String>, Desc: Into<String>>(name: Name, description: Desc) -> Self {
+    fn new<Name: Into<String>>(
+        name: Name,
+        description: Cached<PokemonDescription>,
+        shakespearese: ShakespeareseDescription,
+    ) -> Self {
         PokemonInShakespeareseResponse {
+            response_version: RESPONSE_VERSION,
             name: name.into(),
-            description: description.into(),
+            description: shakespearese.text,
+            translated: shakespearese.engine.is_some(),
+            translator_engine: shakespearese.engine,
+            game_version: description.value.version,
+            language: description.value.language,
+            served_from_cache: description.from_cache && shakespearese.from_cache,
+            corrected_from: None,
+            localized_name: None,
         }
     }
 }
 
-async fn describe_pokemon(pokemon_name: &str) -> Result<String> {
-    let pokemon_response = query_pokemon_by_name(pokemon_name).await?;
-    if !pokemon_response.status().is_success() {
-        return Err(RequestError::new(
-            pokemon_response.status(),
-            format!(
-                "Failed to find a pokemon {} by url {}",
-                &pokemon_name,
-                pokemon_response.url()
-            ),
-        ));
-    }
+// A flavor text of a Pokémon together with where it comes from
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct PokemonDescription {
+    text: String,
+    version: String,
+    language: String,
+}
+
+// The description as it goes into the response, `engine` is `None` if it stayed untranslated
+struct ShakespeareseDescription {
+    text: String,
+    engine: Option<TranslatorEngine>,
+    from_cache: bool,
+}
 
-    let pokemon_response: PokemonResponse = serde_json::from_str(&pokemon_response.text().await?)?;
-    let description_response = reqwest::get(&pokemon_response.species.url).await?;
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PokemonDescriptionsResponse {
+    name: String,
+    descriptions: Vec<TranslatedFlavorText>,
+    // True when neither Poké API nor the translator had to be asked
+    served_from_cache: bool,
+}
 
-    if !description_response.status().is_success() {
-        return Err(RequestError::new(
-            description_response.status(),
-            format!(
-                "Failed to get a species description for the pokemon {} by url {}",
-                &pokemon_name, &pokemon_response.species.url
-            ),
-        ));
+// `translation` and `translator_engine` are `None` unless the translation was asked for, the
+// language is translated and the translator quota allowed it
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TranslatedFlavorText {
+    text: String,
+    language: String,
+    versions: Vec<String>,
+    translation: Option<String>,
+    translator_engine: Option<TranslatorEngine>,
+}
+
+impl From<FlavorText> for TranslatedFlavorText {
+    fn from(flavor_text: FlavorText) -> Self {
+        TranslatedFlavorText {
+            text: flavor_text.text,
+            language: flavor_text.language,
+            versions: flavor_text.versions,
+            translation: None,
+            translator_engine: None,
+        }
     }
-    let description_response_json: PokemonDescriptionResponse =
-        serde_json::from_str(&description_response.text().await?)?;
+}
 
-    let descriptions = description_response_json
-        .descriptions
-        .into_iter()
-        .filter(|entry| entry.language.name == "en")
-        .collect::<Vec<_>>();
+// Results in the order of the requested names
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PokemonBatchResponse {
+    results: Vec<PokemonBatchResult>,
+}
 
-    // Prefer the _ruby_ version otherwise just go for the longest description
-    descriptions
-        .iter()
-        .find(|entry| entry.version.name == "ruby")
-        .or(descriptions
-            .iter()
-            .max_by_key(|entry| entry.flavor_text.len()))
-        .map(|entry| entry.flavor_text.replace('\n', " "))
-        .ok_or(RequestError::new(
-            http::StatusCode::UNPROCESSABLE_ENTITY,
-            format!(
-                "Couldn't find any information about {} in English",
-                &pokemon_name
-            ),
-        ))
+// Either `pokemon` or `problem` is set, `status` is what a single lookup would have responded with
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PokemonBatchResult {
+    name: String,
+    status: u16,
+    pokemon: Option<PokemonInShakespeareseResponse>,
+    problem: Option<ProblemDetails>,
 }
 
-// Results of Poke API queries can depend on presense or absense of trailing slash, so we better try
-// both options. For example, see
-// https://pokeapi.co/api/v2/pokemon/klink vs https://pokeapi.co/api/v2/pokemon/klink/
-// and https://pokeapi.co/api/v2/pokemon/electrode vs https://pokeapi.co/api/v2/pokemon/electrode/
-async fn query_pokemon_by_name(pokemon_name: &str) -> Result<reqwest::Response> {
-    let pokemon_request_url = format!("https://pokeapi.co/api/v2/pokemon/{}", &pokemon_name);
-    let pokemon_response = reqwest::get(&pokemon_request_url).await?;
-    if !pokemon_response.status().is_success() {
-        let url_with_trailing_slash = pokemon_request_url + "/";
-        let response_with_trailing_slash = reqwest::get(&url_with_trailing_slash).await?;
-        Ok(response_with_trailing_slash)
-    } else {
-        Ok(pokemon_response)
-    }
-}
-
-async fn shakespearise(input: &str) -> Result<String> {
-    let request_url = reqwest::Url::parse_with_params(
-        "https://api.funtranslations.com/translate/shakespeare.json",
-        &[("text", input)],
-    )?;
-    let response = reqwest::get(request_url).await?;
-    if !response.status().is_success() {
-        return Err(RequestError::new(
-            response.status(),
-            "Failed to query Shakespeare API",
-        ));
-    }
-    let response_json: serde_json::Value = serde_json::from_str(&response.text().await?)?;
-    response_json["contents"]["translated"]
-        .as_str()
-        .map(str::to_string)
-        .ok_or(RequestError::new(
-            http::StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to shakespearise the text",
-        ))
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PokemonSuggestResponse {
+    query: String,
+    // Best matches first
+    results: Vec<PokemonName>,
+}
+
+// `remaining` is `None` when the translator has no quota
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TranslatorQuotaResponse {
+    remaining: Option<u32>,
+}
+
+// Describes the pokemon in the first of the preferred languages it has a description in
+async fn describe_pokemon<Source: PokemonSource>(
+    pokemon_source: &Source,
+    pokemon_name: &str,
+    preferences: &DescriptionPreferences,
+    selector: &dyn DescriptionSelector,
+) -> Result<PokemonDescription> {
+    let species = pokemon_source.pokemon_species(pokemon_name).await?;
+    select_pokemon_description(&species, pokemon_name, preferences, selector)
+}
+
+fn select_pokemon_description(
+    description_response_json: &PokemonDescriptionResponse,
+    pokemon_name: &str,
+    preferences: &DescriptionPreferences,
+    selector: &dyn DescriptionSelector,
+) -> Result<PokemonDescription> {
+    preferences
+        .languages
+        .iter()
+        .find_map(|language| {
+            let descriptions = description_response_json
+                .descriptions
+                .iter()
+                .filter(|entry| language::matches_language(language, &entry.language.name))
+                .collect::<Vec<_>>();
+            description_selection::select_description(
+                &descriptions,
+                preferences.version.as_deref(),
+                selector,
+            )
+            .map(|entry| PokemonDescription {
+                text: normalization::normalize_flavor_text(&entry.flavor_text),
+                version: entry.version.name.clone(),
+                language: entry.language.name.clone(),
+            })
+        })
+        .ok_or_else(|| match &preferences.version {
+            Some(version) => RequestError::new(
+                RequestErrorKind::NoDescriptionInVersion,
+                format!(
+                    "Couldn't find any information about {} in {} of {}",
+                    &pokemon_name,
+                    preferences.languages.join(", "),
+                    version
+                ),
+            ),
+            None => RequestError::new(
+                RequestErrorKind::NoDescriptionInLanguage,
+                format!(
+                    "Couldn't find any information about {} in {}",
+                    &pokemon_name,
+                    preferences.languages.join(", ")
+                ),
+            ),
+        })
 }
 
-async fn shakespearise_ignore_rate_limit_error(
-    cache: std::sync::Arc<ResponseCache>,
+async fn shakespearise_ignore_rate_limit_error<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    rate_limit_policy: RateLimitPolicy,
     input_description: String,
-) -> Result<String> {
-    cache
+) -> Result<ShakespeareseDescription> {
+    let result = cache
         .shakespearise(&input_description)
         .await
-        .or_else(|err| {
-            if let RequestError {
-                status: http::StatusCode::TOO_MANY_REQUESTS,
-                ..
-            } = err
-            {
-                Ok(input_description)
-            } else {
-                Err(err)
+        .map(|translation| ShakespeareseDescription {
+            text: translation.value,
+            engine: Some(cache.translator.engine()),
+            from_cache: translation.from_cache,
+        });
+    if let Err(err) = &result {
+        if err.is_rate_limited()
+            && rate_limit_policy != RateLimitPolicy::Error
+            && cache.translation_queue.enqueue(input_description.as_str())
+        {
+            eprintln!("Queued a description for translating once the quota frees up");
+        }
+    }
+    result.or_else(|err| match (err.kind, rate_limit_policy) {
+        (RequestErrorKind::RateLimited, RateLimitPolicy::Untranslated) => {
+            Ok(ShakespeareseDescription {
+                text: input_description,
+                engine: None,
+                from_cache: false,
+            })
+        }
+        // Rule-based translations are not cached, so once the quota is available again
+        // the description gets translated properly
+        (RequestErrorKind::RateLimited, RateLimitPolicy::RuleBased) => {
+            Ok(ShakespeareseDescription {
+                text: RuleBasedTranslator.translate(&input_description),
+                engine: Some(TranslatorEngine::RuleBased),
+                from_cache: false,
+            })
+        }
+        _ => Err(err),
+    })
+}
+
+// Translates queued descriptions while there is translator quota left. The translations end up in
+// the cache, so the following requests for the same Pokémon are served in Shakespearese.
+async fn translate_queued_descriptions<Source: PokemonSource, Shakespeare: Translator>(
+    cache: &ResponseCache<Source, Shakespeare>,
+) -> usize {
+    let mut translated_count = 0;
+    while cache.translator.remaining_quota() != Some(0) {
+        let description = match cache.translation_queue.pop() {
+            Some(description) => description,
+            None => break,
+        };
+        match cache.shakespearise(&description).await {
+            Ok(_) => translated_count += 1,
+            Err(err) if err.is_rate_limited() => {
+                if !cache.translation_queue.requeue(description) {
+                    eprintln!("Dropped a queued description, it is queued again already or the queue is full");
+                }
+                break;
             }
-        })
+            Err(err) => eprintln!("Failed to translate a queued description: {:?}", err),
+        }
+    }
+    if translated_count > 0 {
+        eprintln!("Translated {} queued descriptions", translated_count);
+    }
+    translated_count
+}
+
+async fn run_translation_worker<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    retry_interval: std::time::Duration,
+) {
+    loop {
+        tokio::time::sleep(retry_interval).await;
+        translate_queued_descriptions(&cache).await;
+    }
 }
 
-async fn respond_with_pokemon_in_shakespearese(
-    cache: std::sync::Arc<ResponseCache>,
+async fn respond_with_pokemon_in_shakespearese<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
     pokemon_name: String,
+    preferences: DescriptionPreferences,
+    fuzzy: bool,
 ) -> std::result::Result<impl warp::Reply, warp::Rejection> {
     let request_start_time = std::time::Instant::now();
-    use futures::future::TryFutureExt;
-    let pokemon_name = pokemon_name.to_lowercase();
-    let description_result = cache
-        .describe_pokemon(&pokemon_name)
-        .and_then(|desc| {
-            let cache = cache.clone();
-            async move { shakespearise_ignore_rate_limit_error(cache, desc).await }
-        })
-        .await
-        .and_then(|description| {
-            Ok(serde_json::to_string_pretty(
-                &PokemonInShakespeareseResponse::new(&pokemon_name, description),
-            )?)
-        });
+    let pokemon_name = canonical_name(&pokemon_name);
+    let description_result: Result<(String, String)> = async {
+        let response =
+            pokemon_in_shakespearese(cache.clone(), &config, &pokemon_name, &preferences, fuzzy)
+                .await?;
+        let json_response =
+            serde_json::to_string_pretty(&response).map_err(RequestError::from_internal)?;
+        Ok((json_response, response.language))
+    }
+    .await;
     let response = match description_result {
-        Ok(json_response) => http::response::Builder::new()
+        Ok((json_response, language)) => http::response::Builder::new()
             .header("Content-Type", "application/json; charset=UTF-8")
+            .header("Content-Language", language)
+            .header("Vary", "Accept-Language")
             .status(http::StatusCode::OK)
             .body(json_response)
             .unwrap(),
-        Err(err) => {
-            eprintln!("Request \"{}\" failed with error {:?}", &pokemon_name, &err);
-            http::response::Builder::new()
-                .status(err.status)
-                .header("Content-Type", "text/plain; charset=UTF-8")
-                .body(format!(
-                    "Error {}: {}",
-                    err.status.as_u16(),
-                    err.status.canonical_reason().unwrap_or("Unknown reason")
-                ))
-                .unwrap()
-        }
+        Err(err) => problem_response(&err, &format!("/pokemon/{}", &pokemon_name), &config),
     };
     let request_duration = request_start_time.elapsed();
     eprintln!(
@@ -264,160 +746,931 @@ async fn respond_with_pokemon_in_shakespearese(
     Ok(response)
 }
 
-type ResponseCacheMap = chashmap::CHashMap<String, String>;
-
-struct ResponseCache {
-    descriptions: ResponseCacheMap,
-    shakespearese: ResponseCacheMap,
-}
-
-impl ResponseCache {
-    fn new() -> Self {
-        const EXPECTED_CAPACITY: usize = 1200;
-        ResponseCache {
-            descriptions: chashmap::CHashMap::with_capacity(EXPECTED_CAPACITY),
-            shakespearese: chashmap::CHashMap::with_capacity(EXPECTED_CAPACITY),
-        }
-    }
-
-    async fn shakespearise<'input_lifetime>(
-        &self,
-        input_text: &'input_lifetime str,
-    ) -> Result<String> {
-        Self::call_with_cache(
-            &self.shakespearese,
-            input_text,
-            |input: &'input_lifetime str| async move { shakespearise(input).await },
-        )
-        .await
+// When there is no pokemon with the name, the error suggests the closest known names. With `fuzzy`
+// the closest one gets described instead.
+async fn pokemon_in_shakespearese<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: &Config,
+    pokemon_name: &str,
+    preferences: &DescriptionPreferences,
+    fuzzy: bool,
+) -> Result<PokemonInShakespeareseResponse> {
+    let localized_name = cache.resolve_localized_name(pokemon_name);
+    if let Some(localized_name) = &localized_name {
+        eprintln!(
+            "\"{}\" is the {} name of \"{}\"",
+            pokemon_name, &localized_name.language, &localized_name.pokemon
+        );
     }
-
-    async fn describe_pokemon<'input_lifetime>(
-        &self,
-        pokemon_name: &'input_lifetime str,
-    ) -> Result<String> {
-        Self::call_with_cache(
-            &self.descriptions,
-            pokemon_name,
-            |input: &'input_lifetime str| async move { describe_pokemon(input).await },
-        )
+    let pokemon_name = localized_name
+        .as_ref()
+        .map_or(pokemon_name, |localized_name| {
+            localized_name.pokemon.as_str()
+        });
+    let result = match describe_in_shakespearese(cache.clone(), config, pokemon_name, preferences)
         .await
-    }
-
-    async fn call_with_cache<'input_lifetime, F, Future>(
-        cache_map: &ResponseCacheMap,
-        input: &'input_lifetime str,
-        obtain_value: F,
-    ) -> Result<String>
-    where
-        F: Fn(&'input_lifetime str) -> Future,
-        Future: futures::future::Future<Output = Result<String>>,
     {
-        match Self::get_cached_value(cache_map, input) {
-            Some(value) => {
-                eprintln!("Cache hit for \"{}\"", input);
-                Ok(value)
-            }
-            None => match obtain_value(input).await {
-                Ok(value) => {
-                    Self::put_value_in_cache(cache_map, input, value.clone());
-                    Ok(value)
+        Err(err) if err.kind == RequestErrorKind::NotFound => {
+            let suggestions = cache.name_suggestions(pokemon_name).await;
+            match suggestions.first() {
+                Some(closest_name) if fuzzy => {
+                    eprintln!(
+                        "There is no pokemon \"{}\", describing \"{}\" instead",
+                        pokemon_name, closest_name
+                    );
+                    let response =
+                        describe_in_shakespearese(cache, config, closest_name, preferences).await?;
+                    Ok(PokemonInShakespeareseResponse {
+                        corrected_from: Some(pokemon_name.to_string()),
+                        ..response
+                    })
                 }
-                err => err,
-            },
+                _ => Err(err.with_suggestions(suggestions)),
+            }
         }
-    }
-
-    fn get_cached_value(cache: &ResponseCacheMap, key: &str) -> Option<String> {
-        use core::ops::Deref;
-        cache.get(key).map(|lock| lock.deref().to_string())
-    }
-
-    fn put_value_in_cache<Key: Into<String>, Value: Into<String>>(
-        cache: &ResponseCacheMap,
-        key: Key,
-        value: Value,
-    ) {
-        cache.insert_new(key.into(), value.into());
-    }
-}
-
-fn make_internal_error<E: std::error::Error>(error: E) -> RequestError {
-    RequestError::new_internal(format!("{:?}", error))
+        result => result,
+    };
+    result.map(|response| PokemonInShakespeareseResponse {
+        localized_name: localized_name.map(|localized_name| RequestedLocalizedName {
+            name: localized_name.name,
+            language: localized_name.language,
+        }),
+        ..response
+    })
 }
 
-impl std::convert::From<reqwest::Error> for RequestError {
-    fn from(error: reqwest::Error) -> Self {
-        make_internal_error(error)
-    }
+async fn describe_in_shakespearese<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: &Config,
+    pokemon_name: &str,
+    preferences: &DescriptionPreferences,
+) -> Result<PokemonInShakespeareseResponse> {
+    let description = cache.describe_pokemon(pokemon_name, preferences).await?;
+    let shakespearese =
+        if language::is_one_of(&description.value.language, &config.translated_languages) {
+            shakespearise_ignore_rate_limit_error(
+                cache.clone(),
+                config.rate_limit_policy,
+                description.value.text.clone(),
+            )
+            .await?
+        } else {
+            // The translator wasn't needed, so it doesn't stand in the way of serving from cache
+            ShakespeareseDescription {
+                text: description.value.text.clone(),
+                engine: None,
+                from_cache: true,
+            }
+        };
+    Ok(PokemonInShakespeareseResponse::new(
+        pokemon_name,
+        description,
+        shakespearese,
+    ))
 }
 
-impl std::convert::From<serde_json::Error> for RequestError {
-    fn from(error: serde_json::Error) -> Self {
-        make_internal_error(error)
+// Looks up `batch_concurrency` Pokémon at a time, so a large batch doesn't flood the upstreams.
+// Repeated names are looked up once.
+async fn respond_with_batch<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+    names: Vec<String>,
+    preferences: DescriptionPreferences,
+    fuzzy: bool,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    use futures::StreamExt;
+    let request_start_time = std::time::Instant::now();
+    let mut pokemon_names: Vec<String> = Vec::new();
+    for name in names.iter() {
+        let name = canonical_name(name);
+        if !name.is_empty() && !pokemon_names.contains(&name) {
+            pokemon_names.push(name);
+        }
     }
-}
-
-impl std::convert::From<url::ParseError> for RequestError {
-    fn from(error: url::ParseError) -> Self {
-        make_internal_error(error)
+    if pokemon_names.is_empty() || pokemon_names.len() > config.batch_max_size {
+        let err = RequestError::new(
+            RequestErrorKind::InvalidBatch,
+            format!(
+                "The batch names {} Pokémon, expected 1 to {}",
+                pokemon_names.len(),
+                config.batch_max_size
+            ),
+        );
+        return Ok(problem_response(&err, "/pokemon/batch", &config));
     }
+    let batch_size = pokemon_names.len();
+    let results = futures::stream::iter(pokemon_names)
+        .map(|pokemon_name| {
+            let cache = cache.clone();
+            let config = config.clone();
+            let preferences = preferences.clone();
+            async move {
+                let result =
+                    pokemon_in_shakespearese(cache, &config, &pokemon_name, &preferences, fuzzy)
+                        .await;
+                match result {
+                    Ok(pokemon) => PokemonBatchResult {
+                        name: pokemon_name,
+                        status: http::StatusCode::OK.as_u16(),
+                        pokemon: Some(pokemon),
+                        problem: None,
+                    },
+                    Err(err) => PokemonBatchResult {
+                        status: err.status().as_u16(),
+                        pokemon: None,
+                        problem: Some(log_problem(
+                            &err,
+                            &format!("/pokemon/{}", pokemon_name),
+                            &config,
+                        )),
+                        name: pokemon_name,
+                    },
+                }
+            }
+        })
+        .buffered(config.batch_concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+    let response = match serde_json::to_string_pretty(&PokemonBatchResponse { results }) {
+        Ok(json_response) => http::response::Builder::new()
+            .header("Content-Type", "application/json; charset=UTF-8")
+            .header("Vary", "Accept-Language")
+            .status(http::StatusCode::OK)
+            .body(json_response)
+            .unwrap(),
+        Err(err) => problem_response(&RequestError::from_internal(err), "/pokemon/batch", &config),
+    };
+    eprintln!(
+        "Batch request of {} Pokémon took {} ms",
+        batch_size,
+        request_start_time.elapsed().as_millis()
+    );
+    Ok(response)
 }
 
-impl std::fmt::Display for RequestError {
-    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            formatter,
-            "RequestError with status {} and message \"{}\"",
-            self.status, &self.description
+async fn respond_with_picked_pokemon<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+    pick: PokemonPick,
+    preferences: DescriptionPreferences,
+) -> std::result::Result<warp::reply::Response, warp::Rejection> {
+    use warp::Reply;
+    let picked_name = cache.name_index().await.and_then(|name_index| {
+        pick.pick(
+            &name_index,
+            pokemon_of_the_day::utc_day(std::time::SystemTime::now()),
         )
+        .ok_or_else(|| {
+            RequestError::new(
+                RequestErrorKind::NotFound,
+                "There are no pokemon to pick from",
+            )
+        })
+    });
+    match picked_name {
+        Ok(pokemon_name) => {
+            respond_with_pokemon_in_shakespearese(cache, config, pokemon_name, preferences, false)
+                .await
+                .map(Reply::into_response)
+        }
+        Err(err) => Ok(problem_response(&err, pick.path(), &config).into_response()),
     }
 }
 
-impl std::error::Error for RequestError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+// Describes the Pokémon of the day as soon as the UTC day starts, in the configured languages, so
+// that its description and translation are already cached when clients ask for it
+async fn run_daily_pokemon_warmup<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+) {
+    let retry_interval = std::time::Duration::from_secs(config.translation_retry_interval.max(1));
+    let mut day = pokemon_of_the_day::utc_day(std::time::SystemTime::now());
+    loop {
+        let warmed_up = warm_up_daily_pokemon(cache.clone(), &config, day).await;
+        let until_next_day =
+            pokemon_of_the_day::until_utc_day(day + 1, std::time::SystemTime::now());
+        // A failed warm-up is retried until it works, unless the next day comes first
+        if !warmed_up && retry_interval < until_next_day {
+            tokio::time::sleep(retry_interval).await;
+            continue;
+        }
+        tokio::time::sleep(until_next_day).await;
+        // Counted on rather than read off the clock, which may be a little behind the timer, but
+        // without warming up the days missed while the process was suspended
+        day = (day + 1).max(pokemon_of_the_day::utc_day(std::time::SystemTime::now()));
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// Whether the Pokémon of the day is cached now with its translation, or there is none to pick
+async fn warm_up_daily_pokemon<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: &Config,
+    day: u64,
+) -> bool {
+    let pick = PokemonPick::Daily { seed: None };
+    let pokemon_name = match cache
+        .name_index()
+        .await
+        .map(|name_index| pick.pick(&name_index, day))
+    {
+        Ok(Some(pokemon_name)) => pokemon_name,
+        Ok(None) => return true,
+        Err(err) => {
+            eprintln!("Failed to pick the pokemon of the day: {:?}", err);
+            return false;
+        }
+    };
+    // The same preferences as of a request without `lang` and `Accept-Language`
+    let preferences = DescriptionPreferences::new(language::preferred_languages(
+        None,
+        None,
+        &config.description_languages,
+    ));
+    match pokemon_in_shakespearese(cache, config, &pokemon_name, &preferences, false).await {
+        // Served untranslated, e.g. since the translator quota is used up
+        Ok(response)
+            if !response.translated
+                && language::is_one_of(&response.language, &config.translated_languages) =>
+        {
+            eprintln!(
+                "Failed to translate the pokemon of the day \"{}\"",
+                &pokemon_name
+            );
+            false
+        }
+        Ok(_) => {
+            eprintln!("Warmed up the pokemon of the day \"{}\"", &pokemon_name);
+            true
+        }
+        Err(err) => {
+            eprintln!(
+                "Failed to warm up the pokemon of the day \"{}\": {:?}",
+                &pokemon_name, err
+            );
+            false
+        }
+    }
+}
+
+// Served from the name index alone, so after the index is loaded it takes no upstream requests
+async fn respond_with_name_suggestions<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+    query: SuggestQuery,
+) -> std::result::Result<http::Response<String>, warp::Rejection> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SUGGEST_LIMIT)
+        .min(MAX_SUGGEST_LIMIT);
+    let suggestions_result: Result<String> = async {
+        let name_index = cache.name_index().await?;
+        let results = name_index
+            .search(&canonical_name(&query.q), limit)
+            .into_iter()
+            .cloned()
+            .collect();
+        serde_json::to_string_pretty(&PokemonSuggestResponse {
+            query: query.q.clone(),
+            results,
+        })
+        .map_err(RequestError::from_internal)
+    }
+    .await;
+    Ok(match suggestions_result {
+        Ok(json_response) => http::response::Builder::new()
+            .header("Content-Type", "application/json; charset=UTF-8")
+            .status(http::StatusCode::OK)
+            .body(json_response)
+            .unwrap(),
+        Err(err) => problem_response(&err, "/pokemon/suggest", &config),
+    })
+}
+
+async fn respond_with_all_descriptions<Source: PokemonSource, Shakespeare: Translator>(
+    cache: std::sync::Arc<ResponseCache<Source, Shakespeare>>,
+    config: std::sync::Arc<Config>,
+    pokemon_name: String,
+    query: DescriptionsQuery,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let pokemon_name = canonical_name(&pokemon_name);
+    let pokemon_name = cache
+        .resolve_localized_name(&pokemon_name)
+        .map_or(pokemon_name, |localized_name| localized_name.pokemon);
+    let languages = query
+        .lang
+        .as_deref()
+        .map(|lang| language::preferred_languages(Some(lang), None, &[]));
+    let descriptions_result: Result<String> = async {
+        let flavor_texts = cache.all_flavor_texts(&pokemon_name).await?;
+        let mut served_from_cache = flavor_texts.from_cache;
+        let mut descriptions = flavor_texts
+            .value
+            .into_iter()
+            .filter(|flavor_text| match &languages {
+                Some(languages) => languages
+                    .iter()
+                    .any(|language| language::matches_language(language, &flavor_text.language)),
+                None => true,
+            })
+            .map(TranslatedFlavorText::from)
+            .collect::<Vec<_>>();
+        if query.translate.unwrap_or(false) {
+            // Translations are cached, so the quota only limits the texts not translated before
+            for description in descriptions.iter_mut().filter(|description| {
+                language::is_one_of(&description.language, &config.translated_languages)
+            }) {
+                match cache.shakespearise(&description.text).await {
+                    Ok(translation) => {
+                        served_from_cache &= translation.from_cache;
+                        description.translation = Some(translation.value);
+                        description.translator_engine = Some(cache.translator.engine());
+                    }
+                    Err(err) if err.is_rate_limited() => {}
+                    Err(err) => eprintln!(
+                        "Failed to translate a description of {}: {:?}",
+                        &pokemon_name, err
+                    ),
+                }
+            }
+        }
+        serde_json::to_string_pretty(&PokemonDescriptionsResponse {
+            name: pokemon_name.clone(),
+            descriptions,
+            served_from_cache,
+        })
+        .map_err(RequestError::from_internal)
+    }
+    .await;
+    Ok(match descriptions_result {
+        Ok(json_response) => http::response::Builder::new()
+            .header("Content-Type", "application/json; charset=UTF-8")
+            .status(http::StatusCode::OK)
+            .body(json_response)
+            .unwrap(),
+        Err(err) => problem_response(
+            &err,
+            &format!("/pokemon/{}/descriptions", &pokemon_name),
+            &config,
+        ),
+    })
+}
+
+fn problem_response(err: &RequestError, instance: &str, config: &Config) -> http::Response<String> {
+    log_problem(err, instance, config).into_response()
+}
+
+fn log_problem(err: &RequestError, instance: &str, config: &Config) -> ProblemDetails {
+    let request_id = problem::make_request_id();
+    eprintln!(
+        "Request \"{}\" ({}) failed with error {:?}",
+        instance, &request_id, err
+    );
+    ProblemDetails::new(err, instance, &request_id, config.expose_error_details)
+}
+
+// Cached values together with the requests currently obtaining them, so that concurrent misses of
+// the same key make a single upstream request
+struct ResponseCacheMap {
+    values: CacheMap,
+    in_flight: SingleFlight<Cached<String>>,
+}
+
+// A value together with whether it was found in the cache rather than obtained from upstream
+#[derive(Clone, Debug)]
+struct Cached<T> {
+    value: T,
+    from_cache: bool,
+}
+
+impl From<CacheMap> for ResponseCacheMap {
+    fn from(values: CacheMap) -> Self {
+        ResponseCacheMap {
+            values,
+            in_flight: SingleFlight::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ResponseCacheMap {
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+const MAX_NAME_SUGGESTIONS: usize = 3;
+// Loading localized names takes a request per species, so it goes easy on Poké API
+const LOCALIZED_NAMES_CONCURRENCY: usize = 4;
+const LOCALIZED_NAMES_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+struct ResponseCache<Source: PokemonSource, Shakespeare: Translator> {
+    descriptions: ResponseCacheMap,
+    shakespearese: ResponseCacheMap,
+    pokemon_source: Source,
+    translator: Shakespeare,
+    translation_queue: TranslationQueue,
+    description_selector: Box<dyn DescriptionSelector>,
+    // Loaded on first use and kept for the lifetime of the process, new Pokémon are rare
+    name_index: std::sync::RwLock<Option<std::sync::Arc<NameIndex>>>,
+    name_index_in_flight: SingleFlight<std::sync::Arc<NameIndex>>,
+    // Woken up once the name index is loaded
+    name_index_listeners: std::sync::Mutex<Vec<futures::channel::oneshot::Sender<()>>>,
+}
+
+impl<Source: PokemonSource, Shakespeare: Translator> ResponseCache<Source, Shakespeare> {
+    fn new(config: &Config, pokemon_source: Source, translator: Shakespeare) -> Self {
+        let description_settings = cache_settings(config, config.description_cache_ttl);
+        let translation_settings = cache_settings(config, config.translation_cache_ttl);
+        let persistent_store = config.cache_path.as_ref().and_then(|path| {
+            PersistentStore::open(path)
+                .map_err(|err| {
+                    eprintln!(
+                        "Failed to open cache file {}, caching in memory only: {:?}",
+                        path.display(),
+                        err
+                    )
+                })
+                .ok()
+        });
+        let (descriptions, shakespearese) = match persistent_store {
+            Some((store, stored_entries)) => {
+                let store = std::sync::Arc::new(store);
+                (
+                    CacheMap::with_persistent_store(
+                        description_settings,
+                        store.clone(),
+                        "descriptions",
+                        &stored_entries,
+                    ),
+                    CacheMap::with_persistent_store(
+                        translation_settings,
+                        store,
+                        "shakespearese",
+                        &stored_entries,
+                    ),
+                )
+            }
+            None => (
+                CacheMap::new(description_settings),
+                CacheMap::new(translation_settings),
+            ),
+        };
+        ResponseCache {
+            descriptions: ResponseCacheMap::from(descriptions),
+            shakespearese: ResponseCacheMap::from(shakespearese),
+            pokemon_source,
+            translator,
+            translation_queue: TranslationQueue::new(config.translation_queue_capacity),
+            description_selector: description_selection::make_description_selector(config),
+            name_index: std::sync::RwLock::new(None),
+            name_index_in_flight: SingleFlight::default(),
+            name_index_listeners: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    // A failure to load the index isn't remembered, so the next call tries again
+    async fn name_index(&self) -> Result<std::sync::Arc<NameIndex>> {
+        if let Some(name_index) = self.name_index.read().unwrap().clone() {
+            return Ok(name_index);
+        }
+        let name_index = self
+            .name_index_in_flight
+            .run("names", || async {
+                let names = self.pokemon_source.pokemon_names().await?;
+                eprintln!("Loaded {} pokemon names", names.len());
+                Ok(std::sync::Arc::new(NameIndex::new(names)))
+            })
+            .await?;
+        *self.name_index.write().unwrap() = Some(name_index.clone());
+        for listener in self.name_index_listeners.lock().unwrap().drain(..) {
+            let _ = listener.send(());
+        }
+        Ok(name_index)
+    }
+
+    async fn name_index_loaded(&self) {
+        let receiver = {
+            let mut listeners = self.name_index_listeners.lock().unwrap();
+            if self.name_index.read().unwrap().is_some() {
+                return;
+            }
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            listeners.push(sender);
+            receiver
+        };
+        let _ = receiver.await;
+    }
+
+    // Adds the names of every species in all languages to the name index. Waits for the index to
+    // load, either on demand or by trying again every `retry_interval`, and then tries the species
+    // that failed to load again every `retry_interval` until all are there.
+    async fn load_localized_names(&self, retry_interval: std::time::Duration) {
+        let name_index = loop {
+            match self.name_index().await {
+                Ok(name_index) => break name_index,
+                Err(err) => eprintln!("Failed to load pokemon names, will retry: {:?}", err),
+            }
+            futures::future::select(
+                Box::pin(self.name_index_loaded()),
+                Box::pin(tokio::time::sleep(retry_interval)),
+            )
+            .await;
+        };
+        let mut species = name_index
+            .numbered_names()
+            .map(|(number, pokemon)| (number, pokemon.to_string()))
+            .collect::<Vec<_>>();
+        loop {
+            species = self.load_species_names(species).await;
+            if species.is_empty() {
+                return;
+            }
+            eprintln!(
+                "Failed to load localized names of {} pokemon, will retry",
+                species.len()
+            );
+            tokio::time::sleep(retry_interval).await;
+        }
+    }
+
+    // Fetches the localized names of a few species at a time and adds them to the name index.
+    // Returns the species that failed to load.
+    async fn load_species_names(&self, species: Vec<(u32, String)>) -> Vec<(u32, String)> {
+        use futures::StreamExt;
+        let results = futures::stream::iter(species)
+            .map(|(number, pokemon)| async move {
+                let result = self.pokemon_source.species_names(number).await;
+                (number, pokemon, result)
+            })
+            .buffer_unordered(LOCALIZED_NAMES_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        let mut localized_names = Vec::new();
+        let mut failed_species = Vec::new();
+        for (number, pokemon, result) in results {
+            match result {
+                Ok(names) => localized_names.extend(names.into_iter().map(|name| LocalizedName {
+                    name: name.name,
+                    language: name.language.name,
+                    pokemon: pokemon.clone(),
+                })),
+                Err(err) => {
+                    eprintln!("Failed to load localized names of {}: {:?}", &pokemon, err);
+                    failed_species.push((number, pokemon));
+                }
+            }
+        }
+        eprintln!("Loaded {} localized pokemon names", localized_names.len());
+        let mut name_index = self.name_index.write().unwrap();
+        if let Some(loaded_index) = name_index.as_ref() {
+            let loaded_index = (**loaded_index)
+                .clone()
+                .with_localized_names(localized_names);
+            *name_index = Some(std::sync::Arc::new(loaded_index));
+        }
+        failed_species
+    }
+
+    // Only once the index is loaded, a lookup by name shouldn't wait for the whole list of names
+    fn resolve_localized_name(&self, pokemon_name: &str) -> Option<LocalizedName> {
+        self.name_index
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|name_index| name_index.resolve_localized(pokemon_name).cloned())
+    }
+
+    // No suggestions rather than an error when the index can't be loaded, the lookup failed anyway
+    async fn name_suggestions(&self, pokemon_name: &str) -> Vec<String> {
+        match self.name_index().await {
+            Ok(name_index) => name_index.suggestions(pokemon_name, MAX_NAME_SUGGESTIONS),
+            Err(err) => {
+                eprintln!("Failed to load pokemon names: {:?}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn shakespearise<'input_lifetime>(
+        &self,
+        input_text: &'input_lifetime str,
+    ) -> Result<Cached<String>> {
+        Self::call_with_cache(
+            &self.shakespearese,
+            input_text,
+            |input: &'input_lifetime str| async move { self.translator.shakespearise(input).await },
+        )
+        .await
+    }
+
+    async fn describe_pokemon(
+        &self,
+        pokemon_name: &str,
+        preferences: &DescriptionPreferences,
+    ) -> Result<Cached<PokemonDescription>> {
+        // A random pick is made anew for every request, only the flavor texts to pick from are
+        // cached
+        if !self.description_selector.is_deterministic() {
+            let species = self.pokemon_species(pokemon_name).await?;
+            return Ok(Cached {
+                value: select_pokemon_description(
+                    &species.value,
+                    pokemon_name,
+                    preferences,
+                    self.description_selector.as_ref(),
+                )?,
+                from_cache: species.from_cache,
+            });
+        }
+        // Descriptions are cached as JSON, since the cache only keeps strings
+        let cache_key = description_cache_key(
+            pokemon_name,
+            preferences,
+            self.description_selector.as_ref(),
+        );
+        let cached = Self::call_with_cache(&self.descriptions, &cache_key, |_| async move {
+            let description = describe_pokemon(
+                &self.pokemon_source,
+                pokemon_name,
+                preferences,
+                self.description_selector.as_ref(),
+            )
+            .await?;
+            serde_json::to_string(&description).map_err(RequestError::from_internal)
+        })
+        .await?;
+        Ok(Cached {
+            value: serde_json::from_str(&cached.value).map_err(RequestError::from_internal)?,
+            from_cache: cached.from_cache,
+        })
+    }
+
+    // Cached as JSON next to the descriptions, under keys like `pikachu#species`
+    async fn pokemon_species(
+        &self,
+        pokemon_name: &str,
+    ) -> Result<Cached<PokemonDescriptionResponse>> {
+        let cache_key = format!("{}#species", pokemon_name);
+        let cached = Self::call_with_cache(&self.descriptions, &cache_key, |_| async move {
+            let species = self.pokemon_source.pokemon_species(pokemon_name).await?;
+            serde_json::to_string(&species).map_err(RequestError::from_internal)
+        })
+        .await?;
+        Ok(Cached {
+            value: serde_json::from_str(&cached.value).map_err(RequestError::from_internal)?,
+            from_cache: cached.from_cache,
+        })
+    }
+
+    // Cached as JSON next to the descriptions, under keys like `pikachu#all`
+    async fn all_flavor_texts(&self, pokemon_name: &str) -> Result<Cached<Vec<FlavorText>>> {
+        let cache_key = format!("{}#all", pokemon_name);
+        let cached = Self::call_with_cache(&self.descriptions, &cache_key, |_| async move {
+            let species = self.pokemon_source.pokemon_species(pokemon_name).await?;
+            serde_json::to_string(&flavor_texts::group_flavor_texts(&species))
+                .map_err(RequestError::from_internal)
+        })
+        .await?;
+        Ok(Cached {
+            value: serde_json::from_str(&cached.value).map_err(RequestError::from_internal)?,
+            from_cache: cached.from_cache,
+        })
+    }
+
+    async fn call_with_cache<'input_lifetime, F, Future>(
+        cache_map: &ResponseCacheMap,
+        input: &'input_lifetime str,
+        obtain_value: F,
+    ) -> Result<Cached<String>>
+    where
+        F: Fn(&'input_lifetime str) -> Future,
+        Future: futures::future::Future<Output = Result<String>>,
+    {
+        match Self::get_cached_value(cache_map, input) {
+            Some(value) => {
+                eprintln!("Cache hit for \"{}\"", input);
+                Ok(Cached {
+                    value,
+                    from_cache: true,
+                })
+            }
+            // Concurrent callers share the outcome, errors included, but only values get cached
+            None => {
+                let obtain_and_cache_value = || async {
+                    // The value could have arrived while we were waiting for our turn
+                    if let Some(value) = Self::get_cached_value(cache_map, input) {
+                        return Ok(Cached {
+                            value,
+                            from_cache: true,
+                        });
+                    }
+                    match obtain_value(input).await {
+                        Ok(value) => {
+                            Self::put_value_in_cache(cache_map, input, value.clone());
+                            Ok(Cached {
+                                value,
+                                from_cache: false,
+                            })
+                        }
+                        // An expired value is still better than an error
+                        Err(err) => cache_map
+                            .values
+                            .get_stale(input)
+                            .map(|value| Cached {
+                                value,
+                                from_cache: true,
+                            })
+                            .ok_or(err),
+                    }
+                };
+                cache_map.in_flight.run(input, obtain_and_cache_value).await
+            }
+        }
+    }
+
+    fn get_cached_value(cache: &ResponseCacheMap, key: &str) -> Option<String> {
+        cache.values.get(key)
+    }
+
+    fn put_value_in_cache<Key: Into<String>, Value: Into<String>>(
+        cache: &ResponseCacheMap,
+        key: Key,
+        value: Value,
+    ) {
+        cache.values.insert(key, value);
+    }
+}
+
+// The same pokemon may be described differently depending on the languages and the game version
+// asked for and on the description policy, hence keys like `pikachu@de,en~longest` and
+// `pikachu@en/emerald~generation:3`
+fn description_cache_key(
+    pokemon_name: &str,
+    preferences: &DescriptionPreferences,
+    selector: &dyn DescriptionSelector,
+) -> String {
+    let key = format!("{}@{}", pokemon_name, preferences.languages.join(","));
+    let key = match &preferences.version {
+        Some(version) => format!("{}/{}", key, version),
+        None => key,
+    };
+    format!("{}~{}", key, selector.cache_key())
+}
+
+fn cache_settings(config: &Config, time_to_live_seconds: u64) -> CacheSettings {
+    CacheSettings {
+        max_entries: config.cache_capacity,
+        max_bytes: config.cache_max_bytes,
+        time_to_live: Some(time_to_live_seconds)
+            .filter(|seconds| *seconds > 0)
+            .map(std::time::Duration::from_secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use description_selection::Longest;
+    use fakes::{FakePokemonSource, FakeTranslator};
+    use mock_server::MockServer;
+
+    type FakeResponseCache = ResponseCache<FakePokemonSource, FakeTranslator>;
+
+    fn make_http_response_cache(config: &Config) -> ResponseCache<PokeApi, FunTranslationsApi> {
+        let http_client = std::sync::Arc::new(HttpClient::new(config).unwrap());
+        ResponseCache::new(
+            config,
+            PokeApi::new(
+                &config.pokeapi_url,
+                UpstreamClient::new(http_client.clone(), UpstreamSettings::for_pokeapi(config)),
+            ),
+            FunTranslationsApi::new(
+                &config.translator_url,
+                UpstreamClient::new(http_client, UpstreamSettings::for_translator(config)),
+            ),
+        )
+    }
+
+    fn make_fake_response_cache(
+        pokemon_source: FakePokemonSource,
+        translator: FakeTranslator,
+    ) -> FakeResponseCache {
+        ResponseCache::new(&Config::default(), pokemon_source, translator)
+    }
+
+    fn english() -> DescriptionPreferences {
+        DescriptionPreferences::new(vec!["en".to_string()])
+    }
+
+    fn make_fake_pokemon_source() -> FakePokemonSource {
+        FakePokemonSource::default()
+            .with_pokemon(
+                "charizard",
+                &[
+                    (
+                        "red",
+                        "en",
+                        "Spits fire that\nis hot enough to\nmelt boulders.",
+                    ),
+                    (
+                        "ruby",
+                        "en",
+                        "CHARIZARD flies around the sky in\nsearch of powerful opponents.",
+                    ),
+                    ("ruby", "fr", "Dracaufeu parcourt les cieux."),
+                ],
+            )
+            .with_pokemon(
+                "ditto",
+                &[
+                    ("red", "en", "Capable of copying\nan enemy's genetic code."),
+                    (
+                        "blue",
+                        "en",
+                        "Capable of copying an enemy's genetic code to instantly transform itself.",
+                    ),
+                ],
+            )
+            .with_pokemon("missingno", &[("red", "ja", "けつばん")])
+    }
 
     #[tokio::test]
     async fn test_describe_pokemon() {
-        let charizard_description = describe_pokemon("charizard").await;
+        let server = MockServer::start().await;
+        let pokeapi = PokeApi::new(server.pokeapi_url(), Default::default());
+        let charizard_description =
+            describe_pokemon(&pokeapi, "charizard", &english(), &Longest).await;
         assert!(charizard_description.is_ok());
         let charizard_description = charizard_description.unwrap();
-        assert!(charizard_description.len() > 20);
-        assert!(charizard_description.contains("flies"));
+        assert!(charizard_description.text.len() > 20);
+        assert!(charizard_description.text.contains("flies"));
 
-        let banana_description = describe_pokemon("banana").await;
+        let banana_description = describe_pokemon(&pokeapi, "banana", &english(), &Longest).await;
         assert!(banana_description.is_err());
 
-        let empty_request_description = describe_pokemon("").await;
+        let empty_request_description = describe_pokemon(&pokeapi, "", &english(), &Longest).await;
         assert!(empty_request_description.is_err());
 
-        let charizard_by_number = describe_pokemon("6").await;
+        let charizard_by_number = describe_pokemon(&pokeapi, "6", &english(), &Longest).await;
         assert!(charizard_by_number.is_ok());
         assert_eq!(charizard_by_number.unwrap(), charizard_description);
     }
 
     #[tokio::test]
-    #[ignore] // This is flaky because of the low api limits of the service (5 requests per hour)
+    async fn test_describe_pokemon_upstream_unavailable() {
+        // Nothing listens on port 1
+        let pokeapi = PokeApi::new("http://127.0.0.1:1/api/v2", Default::default());
+        let err = describe_pokemon(&pokeapi, "charizard", &english(), &Longest)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, RequestErrorKind::UpstreamConnect);
+        assert_eq!(err.upstream, Some(Upstream::PokeApiPokemon));
+        assert_eq!(err.status(), http::StatusCode::BAD_GATEWAY);
+        use std::error::Error;
+        assert!(err.source().unwrap().is::<reqwest::Error>());
+    }
+
+    #[tokio::test]
+    async fn test_shakespearise_translator_not_found() {
+        let server = MockServer::start().await;
+        // The mock server knows nothing under this url
+        let translator = FunTranslationsApi::new(
+            format!("{}/nowhere", server.translator_url()),
+            Default::default(),
+        );
+        let err = translator
+            .shakespearise("Curiosity killed the cat")
+            .await
+            .unwrap_err();
+        // Not a missing Pokémon, but a translator that doesn't work as expected
+        assert_eq!(
+            err.kind,
+            RequestErrorKind::UpstreamStatus(http::StatusCode::NOT_FOUND)
+        );
+        assert_eq!(err.status(), http::StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
     async fn test_shakespearise() {
-        let cat_phrase = shakespearise("Curiosity killed the cat").await;
+        let server = MockServer::start().await;
+        let translator = FunTranslationsApi::new(server.translator_url(), Default::default());
+        let cat_phrase = translator.shakespearise("Curiosity killed the cat").await;
         assert!(cat_phrase.is_ok());
         assert_eq!(cat_phrase.unwrap(), "Curiosity did kill the gib");
 
-        let empty_phrase = shakespearise("").await;
+        let empty_phrase = translator.shakespearise("").await;
         assert!(empty_phrase.is_ok());
         assert_eq!(empty_phrase.unwrap(), "");
 
-        let rust_phrase = shakespearise(
-            "Rust is a language empowering everyone to build reliable and efficient software.",
-        )
-        .await;
+        let rust_phrase = translator
+            .shakespearise(
+                "Rust is a language empowering everyone to build reliable and efficient software.",
+            )
+            .await;
         assert!(rust_phrase.is_ok());
         assert_eq!(
             rust_phrase.unwrap(),
@@ -427,8 +1680,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_warp_filter() {
-        let cache = std::sync::Arc::new(ResponseCache::new());
-        let filter = pokemon_name_filter(cache.clone());
+        let server = MockServer::start().await;
+        let config = std::sync::Arc::new(server.config());
+        let cache = std::sync::Arc::new(make_http_response_cache(&config));
+        let filter = pokemon_name_filter(cache.clone(), config);
 
         assert!(!warp::test::request().path("/").matches(&filter).await);
         assert!(
@@ -581,8 +1836,8 @@ mod tests {
             .ok()
             .map(
                 |response: PokemonInShakespeareseResponse| PokemonInShakespeareseResponse {
-                    name: response.name,
                     description: response.description.to_lowercase(),
+                    ..response
                 },
             )
     }
@@ -617,7 +1872,14 @@ mod tests {
             .results
             .into_iter()
             .map(|entry| async move {
-                let description = describe_pokemon(&entry.name).await.ok();
+                let description = describe_pokemon(
+                    &PokeApi::new(config::DEFAULT_POKEAPI_URL, Default::default()),
+                    &entry.name,
+                    &english(),
+                    &Longest,
+                )
+                .await
+                .ok();
                 println!(
                     "Name {}, url {}, description {:?}",
                     &entry.name, &entry.url, &description
@@ -640,56 +1902,1266 @@ mod tests {
 
     #[tokio::test]
     async fn test_response_cache_methods() {
-        let cache_map = ResponseCacheMap::new();
-        assert!(ResponseCache::get_cached_value(&cache_map, "banana").is_none());
+        let cache_map = ResponseCacheMap::from(CacheMap::new(CacheSettings::default()));
+        assert!(FakeResponseCache::get_cached_value(&cache_map, "banana").is_none());
 
-        ResponseCache::put_value_in_cache(&cache_map, "banana", "yellow");
-        let cached_banana = ResponseCache::get_cached_value(&cache_map, "banana");
+        FakeResponseCache::put_value_in_cache(&cache_map, "banana", "yellow");
+        let cached_banana = FakeResponseCache::get_cached_value(&cache_map, "banana");
         assert!(cached_banana.is_some());
         assert_eq!(cached_banana.unwrap(), "yellow");
     }
 
     #[tokio::test]
     async fn test_response_cache_not_caching_errors() {
-        let cache = ResponseCache::new();
+        let cache = make_fake_response_cache(FakePokemonSource::default(), FakeTranslator::new());
         assert!(cache.descriptions.is_empty());
         let returned_content =
-            ResponseCache::call_with_cache(&cache.descriptions, "pikachu", |_| {
+            FakeResponseCache::call_with_cache(&cache.descriptions, "pikachu", |_| {
                 futures::future::ready(Ok("pikachu content".to_string()))
             })
             .await;
         assert!(returned_content.is_ok());
-        assert_eq!(returned_content.unwrap(), "pikachu content");
-        let cached_content = ResponseCache::get_cached_value(&cache.descriptions, "pikachu");
+        assert_eq!(returned_content.unwrap().value, "pikachu content");
+        let cached_content = FakeResponseCache::get_cached_value(&cache.descriptions, "pikachu");
         assert!(cached_content.is_some());
         assert_eq!(cached_content.unwrap(), "pikachu content");
 
         let returned_error =
-            ResponseCache::call_with_cache(&cache.descriptions, "charizard", |_| {
+            FakeResponseCache::call_with_cache(&cache.descriptions, "charizard", |_| {
                 futures::future::ready(Err(RequestError::new_internal("charizard error")))
             })
             .await;
         assert!(returned_error.is_err());
         assert_eq!(
-            returned_error.unwrap_err().status,
+            returned_error.unwrap_err().status(),
             http::StatusCode::INTERNAL_SERVER_ERROR
         );
         let cache_response_after_error =
-            ResponseCache::get_cached_value(&cache.descriptions, "charizard");
+            FakeResponseCache::get_cached_value(&cache.descriptions, "charizard");
         assert!(cache_response_after_error.is_none());
     }
 
+    #[tokio::test]
+    async fn test_response_cache_coalescing_requests() {
+        let pokemon_source = FakePokemonSource::default().with_pokemon(
+            "pikachu",
+            &[(
+                "red",
+                "en",
+                "It keeps its tail raised to monitor its surroundings.",
+            )],
+        );
+        let pokemon_source = pokemon_source.with_latency(std::time::Duration::from_millis(20));
+        let cache = make_fake_response_cache(pokemon_source, FakeTranslator::new());
+        let languages = english();
+        let descriptions = futures::future::join_all(
+            (0..20).map(|_| cache.describe_pokemon("pikachu", &languages)),
+        )
+        .await;
+        assert_eq!(cache.pokemon_source.request_count(), 1);
+        for description in descriptions {
+            assert_eq!(
+                description.unwrap().value.text,
+                "It keeps its tail raised to monitor its surroundings."
+            );
+        }
+
+        // Every caller gets the error, but it isn't cached
+        let errors = futures::future::join_all(
+            (0..20).map(|_| cache.describe_pokemon("agumon", &languages)),
+        )
+        .await;
+        assert_eq!(cache.pokemon_source.request_count(), 2);
+        for error in errors {
+            assert_eq!(error.unwrap_err().status(), http::StatusCode::NOT_FOUND);
+        }
+        let _ = cache.describe_pokemon("agumon", &english()).await;
+        assert_eq!(cache.pokemon_source.request_count(), 3);
+    }
+
     #[tokio::test]
     async fn test_response_cache_describe_pokemon() {
-        let cache = ResponseCache::new();
+        let server = MockServer::start().await;
+        let cache = make_http_response_cache(&server.config());
         assert!(cache.descriptions.is_empty());
-        let _ = cache.describe_pokemon("pikachu").await;
+        let _ = cache.describe_pokemon("pikachu", &english()).await;
         assert_eq!(cache.descriptions.len(), 1);
-        let _ = cache.describe_pokemon("charizard").await;
+        let _ = cache.describe_pokemon("charizard", &english()).await;
         assert_eq!(cache.descriptions.len(), 2);
-        let _ = cache.describe_pokemon("charizard").await; // again
+        let _ = cache.describe_pokemon("charizard", &english()).await; // again
         assert_eq!(cache.descriptions.len(), 2);
-        let _ = cache.describe_pokemon("banana").await; // error is not cached
+        let _ = cache.describe_pokemon("banana", &english()).await; // error is not cached
         assert_eq!(cache.descriptions.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_describe_pokemon_offline() {
+        let pokemon_source = make_fake_pokemon_source();
+
+        assert_eq!(
+            describe_pokemon(&pokemon_source, "charizard", &english(), &Longest)
+                .await
+                .unwrap(),
+            PokemonDescription {
+                text: "Charizard flies around the sky in search of powerful opponents.".to_string(),
+                version: "ruby".to_string(),
+                language: "en".to_string(),
+            }
+        );
+        assert_eq!(
+            describe_pokemon(&pokemon_source, "ditto", &english(), &Longest)
+                .await
+                .unwrap()
+                .text,
+            "Capable of copying an enemy's genetic code to instantly transform itself."
+        );
+        assert_eq!(
+            describe_pokemon(&pokemon_source, "missingno", &english(), &Longest)
+                .await
+                .unwrap_err()
+                .status(),
+            http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            describe_pokemon(&pokemon_source, "banana", &english(), &Longest)
+                .await
+                .unwrap_err()
+                .status(),
+            http::StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_warp_filter_offline() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::with_quota(1),
+        ));
+        let filter = pokemon_name_filter(cache.clone(), config);
+
+        let charizard_response = warp::test::request()
+            .path("/pokemon/CharIZard")
+            .reply(&filter)
+            .await;
+        assert_eq!(charizard_response.status(), http::StatusCode::OK);
+        let charizard_description = parse_response(&charizard_response).unwrap();
+        assert_eq!(charizard_description.name, "charizard");
+        assert_eq!(
+            charizard_description.description,
+            "verily, charizard flies around the sky in search of powerful opponents."
+        );
+        assert_eq!(charizard_description.response_version, 4);
+        assert!(charizard_description.corrected_from.is_none());
+        assert!(charizard_description.localized_name.is_none());
+        assert!(charizard_description.translated);
+        assert_eq!(
+            charizard_description.translator_engine,
+            Some(TranslatorEngine::FunTranslations)
+        );
+        assert_eq!(charizard_description.game_version, "ruby");
+        assert_eq!(charizard_description.language, "en");
+        assert!(!charizard_description.served_from_cache);
+
+        // Translator quota is exhausted, so ditto is described in modern English
+        let ditto_response = warp::test::request()
+            .path("/pokemon/ditto")
+            .reply(&filter)
+            .await;
+        assert_eq!(ditto_response.status(), http::StatusCode::OK);
+        let ditto_description = parse_response(&ditto_response).unwrap();
+        assert_eq!(
+            ditto_description.description,
+            "capable of copying an enemy's genetic code to instantly transform itself."
+        );
+        assert!(!ditto_description.translated);
+        assert!(ditto_description.translator_engine.is_none());
+        assert_eq!(ditto_description.game_version, "blue");
+
+        let banana_response = warp::test::request()
+            .path("/pokemon/banana")
+            .reply(&filter)
+            .await;
+        assert_eq!(banana_response.status(), http::StatusCode::NOT_FOUND);
+        assert_eq!(
+            banana_response.headers()["Content-Type"],
+            "application/problem+json; charset=UTF-8"
+        );
+        let banana_problem: ProblemDetails =
+            serde_json::from_slice(banana_response.body()).unwrap();
+        assert_eq!(banana_problem.code, "pokemon-not-found");
+        assert_eq!(banana_problem.instance, "/pokemon/banana");
+        assert_eq!(
+            banana_response.headers()["X-Request-Id"],
+            banana_problem.request_id.as_str()
+        );
+        assert!(banana_problem.internal_detail.is_none());
+
+        // Cached responses don't reach the upstream services
+        let charizard_again_response = warp::test::request()
+            .path("/pokemon/charizard")
+            .reply(&filter)
+            .await;
+        assert_eq!(charizard_again_response.status(), http::StatusCode::OK);
+        assert!(
+            parse_response(&charizard_again_response)
+                .unwrap()
+                .served_from_cache
+        );
+        assert_eq!(cache.pokemon_source.request_count(), 3);
+        assert_eq!(cache.translator.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_canonical_pokemon_names() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            FakePokemonSource::default()
+                .with_pokemon("mr-mime", &[("red", "en", "Mimes things.")])
+                .with_pokemon("farfetchd", &[("red", "en", "Carries a leek.")])
+                .with_pokemon("nidoran-f", &[("red", "en", "Small horn.")])
+                .with_pokemon("flabebe", &[("x", "en", "Rides a flower.")])
+                .with_pokemon("type-null", &[("sun", "en", "Wears a mask.")]),
+            FakeTranslator::new(),
+        ));
+        let filter = pokemon_name_filter(cache.clone(), config);
+        let request_name = |path: &'static str| {
+            let filter = filter.clone();
+            async move {
+                let response = warp::test::request().path(path).reply(&filter).await;
+                assert_eq!(response.status(), http::StatusCode::OK, "path {}", path);
+                parse_response(&response).unwrap()
+            }
+        };
+
+        assert_eq!(request_name("/pokemon/Mr.%20Mime").await.name, "mr-mime");
+        assert_eq!(request_name("/pokemon/Farfetch'd").await.name, "farfetchd");
+        assert_eq!(
+            request_name("/pokemon/Nidoran%E2%99%80").await.name,
+            "nidoran-f"
+        );
+        assert_eq!(
+            request_name("/pokemon/Flab%C3%A9b%C3%A9").await.name,
+            "flabebe"
+        );
+        assert_eq!(
+            request_name("/pokemon/Type:%20Null").await.name,
+            "type-null"
+        );
+        assert_eq!(cache.pokemon_source.request_count(), 5);
+
+        // Spellings of the same Pokémon share the cache entry
+        let mr_mime = request_name("/pokemon/mr-mime").await;
+        assert!(mr_mime.served_from_cache);
+        assert_eq!(cache.pokemon_source.request_count(), 5);
+
+        assert_eq!(
+            warp::test::request()
+                .path("/pokemon/%3F%3F")
+                .reply(&filter)
+                .await
+                .status(),
+            http::StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_localized_names() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source()
+                .with_species_names(
+                    "charizard",
+                    6,
+                    &[("ja", "リザードン"), ("de", "Glurak"), ("en", "Charizard")],
+                )
+                .with_species_names("ditto", 132, &[("fr", "Métamorph"), ("de", "Ditto")]),
+            FakeTranslator::new(),
+        ));
+        let filter = pokemon_name_filter(cache.clone(), config);
+        let request_name = |path: &'static str| {
+            let filter = filter.clone();
+            async move { warp::test::request().path(path).reply(&filter).await }
+        };
+
+        // Localized names are only known once they are loaded
+        assert_eq!(
+            request_name("/pokemon/glurak").await.status(),
+            http::StatusCode::NOT_FOUND
+        );
+        cache
+            .load_localized_names(std::time::Duration::from_millis(10))
+            .await;
+
+        let german_response = request_name("/pokemon/Glurak").await;
+        assert_eq!(german_response.status(), http::StatusCode::OK);
+        let german_description = parse_response(&german_response).unwrap();
+        assert_eq!(german_description.name, "charizard");
+        assert_eq!(
+            german_description.localized_name,
+            Some(RequestedLocalizedName {
+                name: "Glurak".to_string(),
+                language: "de".to_string(),
+            })
+        );
+        // The description language still comes from `lang` and `Accept-Language`
+        assert_eq!(german_description.language, "en");
+
+        let japanese_response =
+            request_name("/pokemon/%E3%83%AA%E3%82%B6%E3%83%BC%E3%83%89%E3%83%B3").await;
+        let japanese_description = parse_response(&japanese_response).unwrap();
+        assert_eq!(japanese_description.name, "charizard");
+        assert_eq!(japanese_description.localized_name.unwrap().language, "ja");
+        // Same cache entry as looking charizard up by its name
+        assert!(japanese_description.served_from_cache);
+
+        let french_response = request_name("/pokemon/metamorph").await;
+        assert_eq!(parse_response(&french_response).unwrap().name, "ditto");
+        let english_response = request_name("/pokemon/ditto").await;
+        assert!(parse_response(&english_response)
+            .unwrap()
+            .localized_name
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_load_localized_names() {
+        let server = MockServer::start().await;
+        let cache = make_http_response_cache(&server.config());
+        cache
+            .load_localized_names(std::time::Duration::from_millis(10))
+            .await;
+        let resolved = |name: &str| {
+            cache
+                .resolve_localized_name(name)
+                .map(|localized_name| localized_name.pokemon)
+        };
+        assert_eq!(resolved("turtok").as_deref(), Some("blastoise"));
+        assert_eq!(resolved("tic").as_deref(), Some("klink"));
+        assert_eq!(resolved("lektrobal").as_deref(), Some("electrode"));
+        assert_eq!(resolved("ピカチュウ").as_deref(), Some("pikachu"));
+        assert_eq!(resolved("pikachu"), None);
+    }
+
+    #[tokio::test]
+    async fn test_load_localized_names_retries() {
+        let make_source = || {
+            make_fake_pokemon_source()
+                .with_species_names("charizard", 6, &[("de", "Glurak")])
+                .with_species_names("ditto", 132, &[("fr", "Métamorph")])
+        };
+        let resolved = |cache: &FakeResponseCache, name: &str| {
+            cache
+                .resolve_localized_name(name)
+                .map(|localized_name| localized_name.pokemon)
+        };
+
+        // Neither the list of names nor the first species load the first time
+        let cache =
+            make_fake_response_cache(make_source().with_name_failures(2), FakeTranslator::new());
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            cache.load_localized_names(std::time::Duration::from_millis(10)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resolved(&cache, "glurak").as_deref(), Some("charizard"));
+        assert_eq!(resolved(&cache, "metamorph").as_deref(), Some("ditto"));
+
+        // Loading the index on demand starts loading the localized names long before the retry
+        let cache =
+            make_fake_response_cache(make_source().with_name_failures(1), FakeTranslator::new());
+        let load_on_demand = async {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            cache.name_index().await.unwrap();
+        };
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            futures::future::join(
+                cache.load_localized_names(std::time::Duration::from_secs(60)),
+                load_on_demand,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resolved(&cache, "glurak").as_deref(), Some("charizard"));
+    }
+
+    #[tokio::test]
+    async fn test_pokemon_suggest_filter() {
+        let server = MockServer::start().await;
+        let config = std::sync::Arc::new(server.config());
+        let cache = std::sync::Arc::new(make_http_response_cache(&config));
+        let filter = pokemon_suggest_filter(cache, config);
+        let suggest = |path: &'static str| {
+            let filter = filter.clone();
+            async move {
+                let response = warp::test::request().path(path).reply(&filter).await;
+                assert_eq!(response.status(), http::StatusCode::OK);
+                serde_json::from_slice::<PokemonSuggestResponse>(response.body()).unwrap()
+            }
+        };
+
+        let suggestions = suggest("/pokemon/suggest?q=Char").await;
+        assert_eq!(suggestions.query, "Char");
+        assert_eq!(
+            suggestions.results,
+            [PokemonName {
+                name: "charizard".to_string(),
+                number: Some(6),
+            }]
+        );
+        let pokeapi_request_count = server.pokeapi_request_count();
+
+        let names = |suggestions: PokemonSuggestResponse| {
+            suggestions
+                .results
+                .into_iter()
+                .map(|result| result.name)
+                .collect::<Vec<_>>()
+        };
+        // Prefix matches go first, then the rest in Pokédex order
+        assert_eq!(
+            names(suggest("/pokemon/suggest?q=e").await),
+            ["electrode", "blastoise"]
+        );
+        assert_eq!(
+            names(suggest("/pokemon/suggest?q=i&limit=2").await),
+            ["charizard", "blastoise"]
+        );
+        assert!(names(suggest("/pokemon/suggest?q=agumon").await).is_empty());
+        assert!(names(suggest("/pokemon/suggest?q=").await).is_empty());
+        assert_eq!(server.pokeapi_request_count(), pokeapi_request_count);
+        assert_eq!(server.translator_request_count(), 0);
+
+        for path in ["/pokemon/suggest", "/pokemon/suggest?q=a&limit=x"] {
+            let response = warp::test::request().path(path).reply(&filter).await;
+            assert_eq!(
+                response.status(),
+                http::StatusCode::BAD_REQUEST,
+                "path {}",
+                path
+            );
+            let problem = serde_json::from_slice::<ProblemDetails>(response.body()).unwrap();
+            assert_eq!(problem.code, "invalid-suggest-query");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_reserved_paths() {
+        let server = MockServer::start().await;
+        let config = std::sync::Arc::new(server.config());
+        let cache = std::sync::Arc::new(make_http_response_cache(&config));
+        let routes = routes(cache, config);
+
+        // Bad queries to the routes of their own don't fall through to looking up a Pokémon called
+        // `suggest`, `random` or `daily`
+        for path in [
+            "/pokemon/suggest",
+            "/pokemon/suggest?q=a&limit=x",
+            "/pokemon/random?fuzzy=maybe",
+            "/pokemon/suggest/",
+        ] {
+            let response = warp::test::request().path(path).reply(&routes).await;
+            assert_eq!(
+                response.status(),
+                http::StatusCode::BAD_REQUEST,
+                "path {}",
+                path
+            );
+        }
+        let response = warp::test::request()
+            .path("/pokemon/suggest")
+            .reply(&routes)
+            .await;
+        let problem = serde_json::from_slice::<ProblemDetails>(response.body()).unwrap();
+        assert_eq!(problem.code, "invalid-suggest-query");
+        assert_eq!(server.pokeapi_request_count(), 0);
+
+        let response = warp::test::request()
+            .path("/pokemon/suggest?q=char")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        let response = warp::test::request()
+            .path("/pokemon/charizard")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_pokemon_pick_filter() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source()
+                .with_species_names("charizard", 6, &[])
+                .with_species_names("ditto", 132, &[]),
+            FakeTranslator::new(),
+        ));
+        let filter = pokemon_pick_filter(cache.clone(), config.clone());
+        let request_pick = |path: &'static str| {
+            let filter = filter.clone();
+            async move {
+                let response = warp::test::request().path(path).reply(&filter).await;
+                assert_eq!(response.status(), http::StatusCode::OK, "path {}", path);
+                parse_response(&response).unwrap()
+            }
+        };
+
+        // Missingno has no Pokédex number, so it never comes up
+        for _ in 0..5 {
+            let random = request_pick("/pokemon/random").await;
+            assert!(["charizard", "ditto"].contains(&random.name.as_str()));
+        }
+
+        let name_index = cache.name_index().await.unwrap();
+        let today = pokemon_of_the_day::utc_day(std::time::SystemTime::now());
+        let expected_daily = |seed: Option<&str>| {
+            PokemonPick::Daily {
+                seed: seed.map(str::to_string),
+            }
+            .pick(&name_index, today)
+            .unwrap()
+        };
+        // Warming up caches both the description and the translation
+        assert!(warm_up_daily_pokemon(cache.clone(), &config, today).await);
+        let translator_request_count = cache.translator.request_count();
+        let daily = request_pick("/pokemon/daily").await;
+        assert_eq!(daily.name, expected_daily(None));
+        assert!(daily.served_from_cache);
+        assert_eq!(cache.translator.request_count(), translator_request_count);
+
+        let seeded = request_pick("/pokemon/daily?seed=team-rocket&lang=fr").await;
+        assert_eq!(seeded.name, expected_daily(Some("team-rocket")));
+        assert_eq!(
+            request_pick("/pokemon/daily?seed=team-rocket&lang=fr")
+                .await
+                .name,
+            seeded.name
+        );
+
+        // The names failed to load, so the warm-up has to be retried
+        let flaky_cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source()
+                .with_species_names("charizard", 6, &[])
+                .with_name_failures(1),
+            FakeTranslator::new(),
+        ));
+        assert!(!warm_up_daily_pokemon(flaky_cache.clone(), &config, today).await);
+        assert!(warm_up_daily_pokemon(flaky_cache, &config, today).await);
+
+        // Nothing translated got cached while the quota was used up
+        let rate_limited_cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source().with_species_names("charizard", 6, &[]),
+            FakeTranslator::with_quota(0),
+        ));
+        assert!(!warm_up_daily_pokemon(rate_limited_cache.clone(), &config, today).await);
+        // The fake counts the rate-limited request against the quota too
+        rate_limited_cache.translator.increase_quota(2);
+        assert!(warm_up_daily_pokemon(rate_limited_cache, &config, today).await);
+
+        // Nothing to pick from
+        let empty_cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        ));
+        let response = warp::test::request()
+            .path("/pokemon/daily")
+            .reply(&pokemon_pick_filter(empty_cache, config))
+            .await;
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_name_suggestions() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        ));
+        let filter = pokemon_name_filter(cache.clone(), config.clone());
+
+        let misspelled_response = warp::test::request()
+            .path("/pokemon/charzard")
+            .reply(&filter)
+            .await;
+        assert_eq!(misspelled_response.status(), http::StatusCode::NOT_FOUND);
+        let problem: ProblemDetails = serde_json::from_slice(misspelled_response.body()).unwrap();
+        assert_eq!(problem.code, "pokemon-not-found");
+        assert_eq!(problem.suggestions, ["charizard"]);
+
+        let corrected_response = warp::test::request()
+            .path("/pokemon/Charzard?fuzzy=true")
+            .reply(&filter)
+            .await;
+        assert_eq!(corrected_response.status(), http::StatusCode::OK);
+        let corrected_description = parse_response(&corrected_response).unwrap();
+        assert_eq!(corrected_description.name, "charizard");
+        assert_eq!(
+            corrected_description.corrected_from.as_deref(),
+            Some("charzard")
+        );
+        assert!(corrected_description.description.contains("flies"));
+
+        // Nothing close enough to correct to
+        let unknown_response = warp::test::request()
+            .path("/pokemon/agumon?fuzzy=true")
+            .reply(&filter)
+            .await;
+        assert_eq!(unknown_response.status(), http::StatusCode::NOT_FOUND);
+        let problem: ProblemDetails = serde_json::from_slice(unknown_response.body()).unwrap();
+        assert!(problem.suggestions.is_empty());
+
+        // Batch lookups get the suggestions too
+        let batch_response = warp::test::request()
+            .path("/pokemon?names=dito,charzard&fuzzy=false")
+            .reply(&pokemon_batch_filter(cache.clone(), config))
+            .await;
+        let batch: PokemonBatchResponse = serde_json::from_slice(batch_response.body()).unwrap();
+        assert_eq!(
+            batch.results[0].problem.as_ref().unwrap().suggestions,
+            ["ditto"]
+        );
+        assert_eq!(
+            batch.results[1].problem.as_ref().unwrap().suggestions,
+            ["charizard"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_name_index_loaded_once() {
+        let server = MockServer::start().await;
+        let cache = std::sync::Arc::new(make_http_response_cache(&server.config()));
+        let name_index = cache.name_index().await.unwrap();
+        assert_eq!(name_index.suggestions("pikachuu", 3), ["pikachu"]);
+        assert_eq!(name_index.suggestions("blastois", 3), ["blastoise"]);
+        let pokeapi_request_count = server.pokeapi_request_count();
+        let suggestions = futures::future::join_all(
+            ["klinc", "electrod"]
+                .iter()
+                .map(|name| cache.name_suggestions(name)),
+        )
+        .await;
+        assert_eq!(suggestions, [["klink"], ["electrode"]]);
+        assert_eq!(server.pokeapi_request_count(), pokeapi_request_count);
+    }
+
+    #[tokio::test]
+    async fn test_description_language() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        ));
+        let filter = pokemon_name_filter(cache.clone(), config);
+
+        // Only English gets translated
+        let french_response = warp::test::request()
+            .path("/pokemon/charizard?lang=fr")
+            .reply(&filter)
+            .await;
+        assert_eq!(french_response.status(), http::StatusCode::OK);
+        assert_eq!(french_response.headers()["Content-Language"], "fr");
+        let french_description = parse_response(&french_response).unwrap();
+        assert_eq!(
+            french_description.description,
+            "dracaufeu parcourt les cieux."
+        );
+        assert_eq!(french_description.language, "fr");
+        assert!(!french_description.translated);
+        assert_eq!(cache.translator.request_count(), 0);
+
+        // Falling back to English when there is no description in the requested language
+        let german_response = warp::test::request()
+            .path("/pokemon/charizard")
+            .header("Accept-Language", "de-DE, de;q=0.9")
+            .reply(&filter)
+            .await;
+        let german_description = parse_response(&german_response).unwrap();
+        assert_eq!(german_description.language, "en");
+        assert!(german_description.translated);
+
+        // The query parameter wins over the header
+        let japanese_response = warp::test::request()
+            .path("/pokemon/missingno?lang=ja")
+            .header("Accept-Language", "fr")
+            .reply(&filter)
+            .await;
+        assert_eq!(parse_response(&japanese_response).unwrap().language, "ja");
+
+        let missing_response = warp::test::request()
+            .path("/pokemon/missingno")
+            .reply(&filter)
+            .await;
+        assert_eq!(
+            missing_response.status(),
+            http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let missing_problem: ProblemDetails =
+            serde_json::from_slice(missing_response.body()).unwrap();
+        assert_eq!(missing_problem.code, "no-description-in-language");
+
+        // A specific game version, if the pokemon has a description from it
+        let red_response = warp::test::request()
+            .path("/pokemon/charizard?version=Red")
+            .reply(&filter)
+            .await;
+        assert_eq!(parse_response(&red_response).unwrap().game_version, "red");
+        let french_ruby_response = warp::test::request()
+            .path("/pokemon/charizard?version=ruby&lang=ja,fr")
+            .reply(&filter)
+            .await;
+        assert_eq!(
+            parse_response(&french_ruby_response).unwrap().language,
+            "fr"
+        );
+        // Rather than quietly describing it from another version
+        let sword_response = warp::test::request()
+            .path("/pokemon/charizard?version=sword")
+            .reply(&filter)
+            .await;
+        assert_eq!(sword_response.status(), http::StatusCode::NOT_FOUND);
+        let sword_problem: ProblemDetails = serde_json::from_slice(sword_response.body()).unwrap();
+        assert_eq!(sword_problem.code, "no-description-in-version");
+        assert!(sword_problem.suggestions.is_empty());
+
+        // Every language and version preference is cached on its own
+        assert_eq!(cache.descriptions.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_pokemon_descriptions_filter() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source().with_pokemon(
+                "pikachu",
+                &[
+                    ("red", "en", "Stores electricity."),
+                    ("blue", "en", "Stores\nelectricity."),
+                    ("yellow", "en", "Lives in forests."),
+                    ("x", "de", "Speichert Strom."),
+                ],
+            ),
+            FakeTranslator::with_quota(1),
+        ));
+        let filter = pokemon_descriptions_filter(cache.clone(), config);
+        let request_descriptions = |path: &'static str| {
+            let filter = filter.clone();
+            async move {
+                let response = warp::test::request().path(path).reply(&filter).await;
+                assert_eq!(response.status(), http::StatusCode::OK);
+                serde_json::from_slice::<PokemonDescriptionsResponse>(response.body()).unwrap()
+            }
+        };
+
+        let all = request_descriptions("/pokemon/Pikachu/descriptions").await;
+        assert_eq!(all.name, "pikachu");
+        assert!(!all.served_from_cache);
+        let versions = all
+            .descriptions
+            .iter()
+            .map(|description| {
+                (
+                    description.language.as_str(),
+                    description.versions.join(","),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            versions,
+            [
+                ("en", "red,blue".to_string()),
+                ("en", "yellow".to_string()),
+                ("de", "x".to_string())
+            ]
+        );
+        assert!(all
+            .descriptions
+            .iter()
+            .all(|description| description.translation.is_none()));
+        assert_eq!(cache.translator.request_count(), 0);
+
+        // Only English gets translated, and only as far as the quota goes
+        let translated =
+            request_descriptions("/pokemon/pikachu/descriptions?translate=true&lang=en").await;
+        assert_eq!(translated.descriptions.len(), 2);
+        assert_eq!(
+            translated.descriptions[0].translation.as_deref(),
+            Some("Verily, Stores electricity.")
+        );
+        assert_eq!(
+            translated.descriptions[0].translator_engine,
+            Some(TranslatorEngine::FunTranslations)
+        );
+        assert!(translated.descriptions[1].translation.is_none());
+        assert_eq!(cache.pokemon_source.request_count(), 1);
+
+        let response = warp::test::request()
+            .path("/pokemon/agumon/descriptions")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+        let problem: ProblemDetails = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(problem.instance, "/pokemon/agumon/descriptions");
+    }
+
+    #[tokio::test]
+    async fn test_pokemon_batch_filter() {
+        let config = std::sync::Arc::new(Config {
+            batch_max_size: 3,
+            ..Config::default()
+        });
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        ));
+        let filter = pokemon_batch_filter(cache.clone(), config);
+
+        // Repeated and blank names don't count
+        let response = warp::test::request()
+            .method("POST")
+            .path("/pokemon/batch")
+            .json(&serde_json::json!({
+                "names": ["Charizard", "agumon", " ", "ditto", "charizard"]
+            }))
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        let batch: PokemonBatchResponse = serde_json::from_slice(response.body()).unwrap();
+        let statuses = batch
+            .results
+            .iter()
+            .map(|result| (result.name.as_str(), result.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [("charizard", 200), ("agumon", 404), ("ditto", 200)]
+        );
+        let charizard = batch.results[0].pokemon.as_ref().unwrap();
+        assert!(charizard.translated);
+        assert!(charizard.description.contains("Charizard flies"));
+        assert!(batch.results[0].problem.is_none());
+        let agumon_problem = batch.results[1].problem.as_ref().unwrap();
+        assert_eq!(agumon_problem.code, "pokemon-not-found");
+        assert_eq!(agumon_problem.instance, "/pokemon/agumon");
+        assert!(batch.results[1].pokemon.is_none());
+        assert_eq!(cache.pokemon_source.request_count(), 3);
+
+        // The same lookup with a query, served from cache
+        let response = warp::test::request()
+            .path("/pokemon?names=ditto,charizard")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        let batch: PokemonBatchResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(batch.results[0].name, "ditto");
+        assert!(batch.results.iter().all(|result| result
+            .pokemon
+            .as_ref()
+            .unwrap()
+            .served_from_cache));
+        assert_eq!(cache.pokemon_source.request_count(), 3);
+
+        // Languages are picked the same way as for a single Pokémon
+        let response = warp::test::request()
+            .path("/pokemon?names=ditto,charizard&lang=fr")
+            .reply(&filter)
+            .await;
+        let batch: PokemonBatchResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(batch.results[0].pokemon.as_ref().unwrap().language, "en");
+        assert_eq!(batch.results[1].pokemon.as_ref().unwrap().language, "fr");
+
+        for body in [
+            serde_json::json!({"names": []}),
+            serde_json::json!({"names": ["", " "]}),
+            serde_json::json!({"names": ["a", "b", "c", "d"]}),
+        ] {
+            let response = warp::test::request()
+                .method("POST")
+                .path("/pokemon/batch")
+                .json(&body)
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+            let problem: ProblemDetails = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(problem.code, "invalid-batch");
+        }
+        assert_eq!(
+            warp::test::request()
+                .method("POST")
+                .path("/pokemon/batch")
+                .body("charizard,ditto")
+                .reply(&filter)
+                .await
+                .status(),
+            http::StatusCode::BAD_REQUEST
+        );
+        assert_eq!(cache.pokemon_source.request_count(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_translator_quota_filter() {
+        let quota = TranslatorQuota::with_limits(&[translator_quota::QuotaLimit {
+            requests: 1,
+            period: std::time::Duration::from_secs(60 * 60),
+        }]);
+        let cache = std::sync::Arc::new(ResponseCache::new(
+            &Config::default(),
+            make_fake_pokemon_source(),
+            QuotaLimitedTranslator::new(FakeTranslator::new(), quota),
+        ));
+        let filter = translator_quota_filter(cache.clone());
+        let remaining_quota = || async {
+            let response = warp::test::request()
+                .path("/translator/quota")
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), http::StatusCode::OK);
+            serde_json::from_slice::<TranslatorQuotaResponse>(response.body())
+                .unwrap()
+                .remaining
+        };
+        assert_eq!(remaining_quota().await, Some(1));
+
+        // The quota is used up, so the description stays in modern English without asking upstream
+        let _ = cache.shakespearise("Hello").await;
+        assert_eq!(remaining_quota().await, Some(0));
+        let untranslated = shakespearise_ignore_rate_limit_error(
+            cache.clone(),
+            RateLimitPolicy::Untranslated,
+            "Goodbye".to_string(),
+        )
+        .await;
+        let untranslated = untranslated.unwrap();
+        assert_eq!(untranslated.text, "Goodbye");
+        assert!(untranslated.engine.is_none());
+
+        let unlimited_cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        ));
+        let response = warp::test::request()
+            .path("/translator/quota")
+            .reply(&translator_quota_filter(unlimited_cache))
+            .await;
+        assert_eq!(response.body(), "{\"remaining\":null}");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_error_policy() {
+        let config = std::sync::Arc::new(Config {
+            rate_limit_policy: RateLimitPolicy::Error,
+            ..Config::default()
+        });
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::with_quota(0),
+        ));
+        let filter = pokemon_name_filter(cache, config);
+        assert_eq!(
+            warp::test::request()
+                .path("/pokemon/ditto")
+                .reply(&filter)
+                .await
+                .status(),
+            http::StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translating_queued_descriptions() {
+        let config = std::sync::Arc::new(Config::default());
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::with_quota(0),
+        ));
+        let filter = pokemon_name_filter(cache.clone(), config);
+        let describe = |name: &'static str| {
+            let filter = filter.clone();
+            async move {
+                let path = format!("/pokemon/{}", name);
+                let response = warp::test::request().path(&path).reply(&filter).await;
+                parse_response(&response).unwrap().description
+            }
+        };
+        let ditto_description =
+            "capable of copying an enemy's genetic code to instantly transform itself.";
+        assert_eq!(describe("ditto").await, ditto_description);
+        assert_eq!(describe("ditto").await, ditto_description);
+        assert_eq!(
+            describe("charizard").await,
+            "charizard flies around the sky in search of powerful opponents."
+        );
+        assert_eq!(cache.translation_queue.len(), 2);
+
+        // Still no quota, the descriptions stay queued
+        assert_eq!(translate_queued_descriptions(&cache).await, 0);
+        assert_eq!(cache.translation_queue.len(), 2);
+
+        // The refused requests count towards the quota as well
+        cache.translator.increase_quota(6);
+        assert_eq!(translate_queued_descriptions(&cache).await, 2);
+        assert_eq!(cache.translation_queue.len(), 0);
+        assert_eq!(
+            describe("ditto").await,
+            "verily, capable of copying an enemy's genetic code to instantly transform itself."
+        );
+        assert_eq!(cache.translator.request_count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_rule_based_policy() {
+        let config = std::sync::Arc::new(Config {
+            rate_limit_policy: RateLimitPolicy::RuleBased,
+            ..Config::default()
+        });
+        let cache = std::sync::Arc::new(make_fake_response_cache(
+            make_fake_pokemon_source(),
+            FakeTranslator::with_quota(0),
+        ));
+        let filter = pokemon_name_filter(cache.clone(), config);
+        let charizard_response = warp::test::request()
+            .path("/pokemon/charizard")
+            .reply(&filter)
+            .await;
+        assert_eq!(charizard_response.status(), http::StatusCode::OK);
+        assert_eq!(
+            parse_response(&charizard_response).unwrap().description,
+            "charizard flieth around the sky in search of powerful opponents."
+        );
+        assert!(cache.shakespearese.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shakespearise_rate_limit() {
+        let server = MockServer::with_translator_quota(1).await;
+        let translator = FunTranslationsApi::new(server.translator_url(), Default::default());
+        assert!(translator
+            .shakespearise("Curiosity killed the cat")
+            .await
+            .is_ok());
+        assert_eq!(
+            translator
+                .shakespearise("Curiosity killed the cat")
+                .await
+                .unwrap_err()
+                .status(),
+            http::StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_warp_filter_rate_limit() {
+        let server = MockServer::with_translator_quota(1).await;
+        let config = std::sync::Arc::new(server.config());
+        let cache = std::sync::Arc::new(make_http_response_cache(&config));
+        let filter = pokemon_name_filter(cache, config);
+
+        let blastoise_response = warp::test::request()
+            .path("/pokemon/blastoise")
+            .reply(&filter)
+            .await;
+        assert_eq!(blastoise_response.status(), http::StatusCode::OK);
+        assert!(parse_response(&blastoise_response)
+            .unwrap()
+            .description
+            .starts_with("blastoise hath water spouts yond protrude"));
+
+        let pikachu_response = warp::test::request()
+            .path("/pokemon/pikachu")
+            .reply(&filter)
+            .await;
+        assert_eq!(pikachu_response.status(), http::StatusCode::OK);
+        assert!(parse_response(&pikachu_response)
+            .unwrap()
+            .description
+            .starts_with("it stores electricity"));
+        assert_eq!(server.translator_request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let fixture_dir = fixtures::make_temp_dir("record-and-replay");
+        let server = MockServer::start().await;
+
+        let record_config = std::sync::Arc::new(Config {
+            fixture_mode: config::FixtureMode::Record,
+            fixture_dir: fixture_dir.clone(),
+            ..server.config()
+        });
+        let record_cache = std::sync::Arc::new(make_http_response_cache(&record_config));
+        let record_filter = pokemon_name_filter(record_cache, record_config.clone());
+        let recorded_response = warp::test::request()
+            .path("/pokemon/klink")
+            .reply(&record_filter)
+            .await;
+        assert_eq!(recorded_response.status(), http::StatusCode::OK);
+        let pokeapi_request_count = server.pokeapi_request_count();
+        let translator_request_count = server.translator_request_count();
+
+        let replay_config = std::sync::Arc::new(Config {
+            fixture_mode: config::FixtureMode::Replay,
+            ..(*record_config).clone()
+        });
+        let replay_cache = std::sync::Arc::new(make_http_response_cache(&replay_config));
+        let replay_filter = pokemon_name_filter(replay_cache, replay_config);
+        let replayed_response = warp::test::request()
+            .path("/pokemon/klink")
+            .reply(&replay_filter)
+            .await;
+        assert_eq!(replayed_response.status(), http::StatusCode::OK);
+        assert_eq!(replayed_response.body(), recorded_response.body());
+
+        // Nothing recorded for pikachu
+        assert_eq!(
+            warp::test::request()
+                .path("/pokemon/pikachu")
+                .reply(&replay_filter)
+                .await
+                .status(),
+            http::StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(server.pokeapi_request_count(), pokeapi_request_count);
+        assert_eq!(server.translator_request_count(), translator_request_count);
+
+        std::fs::remove_dir_all(&fixture_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_refreshing_stale_values() {
+        let cache = ResponseCache::new(
+            &Config {
+                description_cache_ttl: 1,
+                ..Config::default()
+            },
+            FakePokemonSource::default(),
+            FakeTranslator::new(),
+        );
+        let cache_map = &cache.descriptions;
+        FakeResponseCache::put_value_in_cache(cache_map, "pikachu", "old content");
+        FakeResponseCache::put_value_in_cache(cache_map, "charizard", "old content");
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        assert!(FakeResponseCache::get_cached_value(cache_map, "pikachu").is_none());
+
+        let refreshed_content = FakeResponseCache::call_with_cache(cache_map, "pikachu", |_| {
+            futures::future::ready(Ok("new content".to_string()))
+        })
+        .await;
+        assert_eq!(refreshed_content.unwrap().value, "new content");
+        assert_eq!(
+            FakeResponseCache::get_cached_value(cache_map, "pikachu").unwrap(),
+            "new content"
+        );
+
+        // Failing to refresh falls back to the stale value
+        let stale_content = FakeResponseCache::call_with_cache(cache_map, "charizard", |_| {
+            futures::future::ready(Err(RequestError::new_internal("charizard error")))
+        })
+        .await;
+        let stale_content = stale_content.unwrap();
+        assert_eq!(stale_content.value, "old content");
+        assert!(stale_content.from_cache);
+        assert!(FakeResponseCache::get_cached_value(cache_map, "charizard").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_eviction() {
+        let cache = ResponseCache::new(
+            &Config {
+                cache_capacity: 2,
+                ..Config::default()
+            },
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        );
+        for name in ["charizard", "ditto", "charizard", "missingno", "charizard"].iter() {
+            let _ = cache.describe_pokemon(name, &english()).await;
+        }
+        assert_eq!(cache.descriptions.len(), 2);
+        assert!(FakeResponseCache::get_cached_value(
+            &cache.descriptions,
+            &description_cache_key("charizard", &english(), cache.description_selector.as_ref())
+        )
+        .is_some());
+        assert_eq!(cache.pokemon_source.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_random_description_policy() {
+        let cache = ResponseCache::new(
+            &Config {
+                description_policy: config::DescriptionPolicy::Random,
+                ..Config::default()
+            },
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        );
+        let mut versions = std::collections::HashSet::new();
+        for _ in 0..30 {
+            let description = cache
+                .describe_pokemon("charizard", &english())
+                .await
+                .unwrap();
+            versions.insert(description.value.version);
+        }
+        // Each of the two English flavor texts comes up, but Poké API is only asked once
+        assert_eq!(versions.len(), 2);
+        assert_eq!(cache.pokemon_source.request_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_surviving_restart() {
+        let dir = fixtures::make_temp_dir("response-cache-restart");
+        let config = Config {
+            cache_path: Some(dir.join("cache.jsonl")),
+            ..Config::default()
+        };
+
+        let cache = ResponseCache::new(&config, make_fake_pokemon_source(), FakeTranslator::new());
+        let description = cache
+            .describe_pokemon("charizard", &english())
+            .await
+            .unwrap()
+            .value;
+        let translation = cache.shakespearise(&description.text).await.unwrap().value;
+        drop(cache);
+
+        let restarted_cache =
+            ResponseCache::new(&config, make_fake_pokemon_source(), FakeTranslator::new());
+        let restored_description = restarted_cache
+            .describe_pokemon("charizard", &english())
+            .await
+            .unwrap();
+        assert!(restored_description.from_cache);
+        assert_eq!(restored_description.value, description);
+        assert_eq!(
+            restarted_cache
+                .shakespearise(&description.text)
+                .await
+                .unwrap()
+                .value,
+            translation
+        );
+        assert_eq!(restarted_cache.pokemon_source.request_count(), 0);
+        assert_eq!(restarted_cache.translator.request_count(), 0);
+        drop(restarted_cache);
+
+        // Descriptions picked by another policy don't count
+        let reconfigured_cache = ResponseCache::new(
+            &Config {
+                description_policy: config::DescriptionPolicy::Shortest,
+                ..config.clone()
+            },
+            make_fake_pokemon_source(),
+            FakeTranslator::new(),
+        );
+        let reconfigured_description = reconfigured_cache
+            .describe_pokemon("charizard", &english())
+            .await
+            .unwrap();
+        assert!(!reconfigured_description.from_cache);
+        assert_ne!(reconfigured_description.value, description);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,330 @@
+// A local stand-in for Poké API and Shakespeare translator API. It serves the responses recorded in
+// tests/fixtures, so the tests neither depend on the live services nor burn the translator quota.
+//
+// Things worth knowing about the recorded data:
+// - urls in Poké API responses are rewritten to point at the mock server,
+// - the translator responds with recorded translations when it has them and falls back to the
+//   rule-based translator otherwise,
+// - once the translator quota is used up, it responds with `TOO_MANY_REQUESTS` like the real one.
+
+use crate::config::Config;
+use crate::rule_based_translator::RuleBasedTranslator;
+
+const FIXTURES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+const RECORDED_POKEAPI_URL: &str = "https://pokeapi.co/api/v2";
+
+// Poké API finds these pokemon only when the url has a trailing slash
+const TRAILING_SLASH_ONLY: &[&str] = &["klink", "electrode"];
+
+pub struct MockServer {
+    address: std::net::SocketAddr,
+    state: std::sync::Arc<MockServerState>,
+}
+
+struct MockServerState {
+    translator_quota: usize,
+    translator_request_count: std::sync::atomic::AtomicUsize,
+    pokeapi_request_count: std::sync::atomic::AtomicUsize,
+}
+
+#[derive(serde::Deserialize)]
+struct RecordedTranslation {
+    text: String,
+    translated: String,
+}
+
+impl MockServer {
+    // Starts a mock server on a random local port. The server lives as long as the tokio runtime
+    // it was started on, which for `#[tokio::test]` is the duration of the test.
+    pub async fn start() -> MockServer {
+        MockServer::with_translator_quota(usize::MAX).await
+    }
+
+    pub async fn with_translator_quota(translator_quota: usize) -> MockServer {
+        let state = std::sync::Arc::new(MockServerState {
+            translator_quota,
+            translator_request_count: std::sync::atomic::AtomicUsize::new(0),
+            pokeapi_request_count: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let (address, server) =
+            warp::serve(mock_server_filter(state.clone())).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        MockServer { address, state }
+    }
+
+    pub fn pokeapi_url(&self) -> String {
+        format!("http://{}/api/v2", self.address)
+    }
+
+    pub fn translator_url(&self) -> String {
+        format!("http://{}/translate", self.address)
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            pokeapi_url: self.pokeapi_url(),
+            translator_url: self.translator_url(),
+            ..Config::default()
+        }
+    }
+
+    pub fn pokeapi_request_count(&self) -> usize {
+        self.state
+            .pokeapi_request_count
+            .load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    pub fn translator_request_count(&self) -> usize {
+        self.state
+            .translator_request_count
+            .load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+fn mock_server_filter(
+    state: std::sync::Arc<MockServerState>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Filter;
+    warp::get()
+        .and(warp::path::full())
+        .and(warp::header::<String>("host"))
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .map(
+            move |path: warp::path::FullPath,
+                  host: String,
+                  query: std::collections::HashMap<String, String>| {
+                state.respond(path.as_str(), &host, &query)
+            },
+        )
+}
+
+impl MockServerState {
+    fn respond(
+        &self,
+        path: &str,
+        host: &str,
+        query: &std::collections::HashMap<String, String>,
+    ) -> http::Response<String> {
+        let pokeapi_url = format!("http://{}/api/v2", host);
+        let segments = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+        match segments.as_slice() {
+            ["api", "v2", "pokemon"] | ["api", "v2", "pokemon", ""] => {
+                self.count_pokeapi_request();
+                list_pokemon(&pokeapi_url)
+            }
+            ["api", "v2", "pokemon", name] => {
+                self.count_pokeapi_request();
+                if TRAILING_SLASH_ONLY.contains(name) {
+                    return not_found();
+                }
+                find_pokemon(name)
+                    .map(|pokemon| json_response(rewrite_urls(&pokemon, &pokeapi_url)))
+                    .unwrap_or_else(not_found)
+            }
+            ["api", "v2", "pokemon", name, ""] => {
+                self.count_pokeapi_request();
+                find_pokemon(name)
+                    .map(|pokemon| json_response(rewrite_urls(&pokemon, &pokeapi_url)))
+                    .unwrap_or_else(not_found)
+            }
+            ["api", "v2", "pokemon-species", id] | ["api", "v2", "pokemon-species", id, ""]
+                if id.parse::<u64>().is_ok() =>
+            {
+                self.count_pokeapi_request();
+                read_fixture(&format!("pokeapi/pokemon-species/{}.json", id))
+                    .map(|species| json_response(rewrite_urls(&species, &pokeapi_url)))
+                    .unwrap_or_else(not_found)
+            }
+            ["translate", "shakespeare.json"] => self.translate(query.get("text")),
+            _ => not_found(),
+        }
+    }
+
+    fn count_pokeapi_request(&self) {
+        self.pokeapi_request_count
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn translate(&self, text: Option<&String>) -> http::Response<String> {
+        let previous_requests = self
+            .translator_request_count
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if previous_requests >= self.translator_quota {
+            return error_response(
+                http::StatusCode::TOO_MANY_REQUESTS,
+                "Too Many Requests: Rate limit of 5 requests per hour exceeded. \
+                 Please wait for 59 minutes and 59 seconds.",
+            );
+        }
+        let text = match text {
+            Some(text) => text,
+            None => {
+                return error_response(
+                    http::StatusCode::BAD_REQUEST,
+                    "Bad Request: text is missing.",
+                )
+            }
+        };
+        let translated =
+            recorded_translation(text).unwrap_or_else(|| RuleBasedTranslator.translate(text));
+        json_response(
+            serde_json::json!({
+                "success": { "total": 1 },
+                "contents": {
+                    "translated": translated,
+                    "text": text,
+                    "translation": "shakespeare",
+                },
+            })
+            .to_string(),
+        )
+    }
+}
+
+fn read_fixture(relative_path: &str) -> Option<String> {
+    std::fs::read_to_string(format!("{}/{}", FIXTURES_DIR, relative_path)).ok()
+}
+
+// Returns recorded pokemon in the order of their Pokédex numbers
+fn all_pokemon() -> Vec<serde_json::Value> {
+    let mut all_pokemon = std::fs::read_dir(format!("{}/pokeapi/pokemon", FIXTURES_DIR))
+        .unwrap()
+        .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path()).ok())
+        .filter_map(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .collect::<Vec<_>>();
+    all_pokemon.sort_by_key(|pokemon| pokemon["id"].as_u64());
+    all_pokemon
+}
+
+// Like Poké API, looks the pokemon up by either name or Pokédex number
+fn find_pokemon(name_or_id: &str) -> Option<String> {
+    if name_or_id.is_empty()
+        || !name_or_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return None;
+    }
+    if let Ok(id) = name_or_id.parse::<u64>() {
+        return all_pokemon()
+            .into_iter()
+            .find(|pokemon| pokemon["id"].as_u64() == Some(id))
+            .map(|pokemon| pokemon.to_string());
+    }
+    read_fixture(&format!("pokeapi/pokemon/{}.json", name_or_id))
+}
+
+fn list_pokemon(pokeapi_url: &str) -> http::Response<String> {
+    let results = all_pokemon()
+        .into_iter()
+        .map(|pokemon| {
+            serde_json::json!({
+                "name": pokemon["name"],
+                "url": format!("{}/pokemon/{}/", pokeapi_url, pokemon["id"]),
+            })
+        })
+        .collect::<Vec<_>>();
+    json_response(
+        serde_json::json!({
+            "count": results.len(),
+            "next": null,
+            "previous": null,
+            "results": results,
+        })
+        .to_string(),
+    )
+}
+
+fn recorded_translation(text: &str) -> Option<String> {
+    // Whitespace may differ depending on how the description was cleaned up before translation
+    let normalize_whitespace = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = normalize_whitespace(text);
+    let recorded: Vec<RecordedTranslation> =
+        serde_json::from_str(&read_fixture("funtranslations/shakespeare.json")?).unwrap();
+    recorded
+        .into_iter()
+        .find(|recording| normalize_whitespace(&recording.text) == text)
+        .map(|recording| recording.translated)
+}
+
+fn rewrite_urls(content: &str, pokeapi_url: &str) -> String {
+    content.replace(RECORDED_POKEAPI_URL, pokeapi_url)
+}
+
+fn json_response(body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Content-Type", "application/json; charset=utf-8")
+        .body(body)
+        .unwrap()
+}
+
+fn error_response(status: http::StatusCode, message: &str) -> http::Response<String> {
+    let mut response = json_response(
+        serde_json::json!({ "error": { "code": status.as_u16(), "message": message } }).to_string(),
+    );
+    *response.status_mut() = status;
+    response
+}
+
+fn not_found() -> http::Response<String> {
+    http::Response::builder()
+        .status(http::StatusCode::NOT_FOUND)
+        .body("Not Found".to_string())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_status(url: String) -> http::StatusCode {
+        reqwest::get(&url).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_mock_pokeapi() {
+        let server = MockServer::start().await;
+        let pokeapi_url = server.pokeapi_url();
+
+        let charizard_response = reqwest::get(&format!("{}/pokemon/charizard", &pokeapi_url))
+            .await
+            .unwrap();
+        let charizard: serde_json::Value =
+            serde_json::from_str(&charizard_response.text().await.unwrap()).unwrap();
+        assert_eq!(
+            charizard["species"]["url"],
+            format!("{}/pokemon-species/6/", &pokeapi_url)
+        );
+        assert_eq!(get_status(format!("{}/pokemon/6", &pokeapi_url)).await, 200);
+        assert_eq!(
+            get_status(format!("{}/pokemon-species/6/", &pokeapi_url)).await,
+            200
+        );
+        assert_eq!(
+            get_status(format!("{}/pokemon/banana", &pokeapi_url)).await,
+            404
+        );
+        assert_eq!(
+            get_status(format!("{}/pokemon/klink", &pokeapi_url)).await,
+            404
+        );
+        assert_eq!(
+            get_status(format!("{}/pokemon/klink/", &pokeapi_url)).await,
+            200
+        );
+        assert_eq!(
+            get_status(format!("{}/pokemon/..%2Fpokemon-species%2F6", &pokeapi_url)).await,
+            404
+        );
+        assert_eq!(server.pokeapi_request_count(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_mock_translator_quota() {
+        let server = MockServer::with_translator_quota(1).await;
+        let url = format!("{}/shakespeare.json?text=Hello", server.translator_url());
+        assert_eq!(get_status(url.clone()).await, 200);
+        assert_eq!(get_status(url).await, http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(server.translator_request_count(), 2);
+    }
+}
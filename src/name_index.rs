@@ -0,0 +1,277 @@
+// Names of all Pokémon Poké API knows, for telling the client what they probably meant when a
+// lookup finds nothing, and for finding Pokémon by their names in other languages. The index is
+// small enough to search through in full on every miss.
+
+use crate::canonical_name::canonical_name;
+
+// Suggestions further away than this many edits are more confusing than helpful
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PokemonName {
+    pub name: String,
+    // `None` for alternative forms like `charizard-mega-x`, which Poké API numbers from 10001 on
+    pub number: Option<u32>,
+}
+
+// A name of a Pokémon in some language, e.g. `Glurak` in `de` for `charizard`
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LocalizedName {
+    pub name: String,
+    pub language: String,
+    // The Poké API name of the Pokémon
+    pub pokemon: String,
+}
+
+#[derive(Clone)]
+pub struct NameIndex {
+    names: Vec<PokemonName>,
+    known_names: std::collections::HashSet<String>,
+    // By their canonical names, which lookups compare against
+    localized_names: std::collections::HashMap<String, LocalizedName>,
+}
+
+impl NameIndex {
+    pub fn new(names: Vec<PokemonName>) -> Self {
+        NameIndex {
+            known_names: names.iter().map(|known| known.name.clone()).collect(),
+            names,
+            localized_names: std::collections::HashMap::new(),
+        }
+    }
+
+    // Adds to the localized names already there. Names that are the same as the Poké API name of
+    // the Pokémon don't need resolving, and a name taken by another Pokémon already stays theirs.
+    pub fn with_localized_names(mut self, localized_names: Vec<LocalizedName>) -> Self {
+        for localized_name in localized_names {
+            let key = canonical_name(&localized_name.name);
+            if key.is_empty() || key == localized_name.pokemon {
+                continue;
+            }
+            self.localized_names.entry(key).or_insert(localized_name);
+        }
+        self
+    }
+
+    // Pokémon with a Pokédex number, which is also the number of their species
+    pub fn numbered_names(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.names
+            .iter()
+            .filter_map(|name| Some((name.number?, name.name.as_str())))
+    }
+
+    // The Pokémon a canonical name stands for in some language, unless there is a Pokémon called
+    // exactly like that
+    pub fn resolve_localized(&self, name: &str) -> Option<&LocalizedName> {
+        if self.known_names.contains(name) {
+            return None;
+        }
+        self.localized_names.get(name)
+    }
+
+    // Pokémon whose names start with the canonical `query`, then those with `query` elsewhere in
+    // the name, each in Pokédex order with alternative forms last
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&PokemonName> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = self
+            .names
+            .iter()
+            .filter_map(|known| {
+                let position = known.name.find(query)?;
+                Some((position > 0, known.number.unwrap_or(u32::MAX), known))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|left, right| {
+            (left.0, left.1, &left.2.name).cmp(&(right.0, right.1, &right.2.name))
+        });
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, known)| known)
+            .collect()
+    }
+
+    // Known names closest to `name` by edit distance, closest first. Short names tolerate fewer
+    // edits, so that `mew` doesn't suggest every three-letter Pokémon.
+    pub fn suggestions(&self, name: &str, limit: usize) -> Vec<String> {
+        let max_distance = (name.chars().count() / 3).clamp(1, MAX_SUGGESTION_DISTANCE);
+        let mut candidates = self
+            .names
+            .iter()
+            .map(|known| (edit_distance(name, &known.name), &known.name))
+            .filter(|(distance, known)| *distance <= max_distance && *known != name)
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(_, known)| known.clone())
+            .collect()
+    }
+}
+
+// Damerau-Levenshtein distance counting insertions, deletions, substitutions and swaps of two
+// adjacent characters as one edit each
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let left = left.chars().collect::<Vec<_>>();
+    let right = right.chars().collect::<Vec<_>>();
+    // distances[i][j] is the distance between the first i characters of left and j of right
+    let mut distances = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let substitution_cost = usize::from(left[i - 1] != right[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution_cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[left.len()][right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_index(names: &[&str]) -> NameIndex {
+        NameIndex::new(
+            names
+                .iter()
+                .enumerate()
+                .map(|(position, name)| PokemonName {
+                    name: name.to_string(),
+                    number: Some(position as u32 + 1),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("charizard", "charizard"), 0);
+        assert_eq!(edit_distance("charzard", "charizard"), 1);
+        assert_eq!(edit_distance("chraizard", "charizard"), 1);
+        assert_eq!(edit_distance("pikachu", "pichu"), 2);
+        assert_eq!(edit_distance("", "mew"), 3);
+        assert_eq!(edit_distance("flabébé", "flabebe"), 2);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let index = make_index(&[
+            "charmander",
+            "charmeleon",
+            "charizard",
+            "pikachu",
+            "pichu",
+            "mew",
+            "mewtwo",
+            "muk",
+        ]);
+        assert_eq!(index.suggestions("charzard", 3), ["charizard"]);
+        assert_eq!(index.suggestions("pikachuu", 3), ["pikachu"]);
+        assert_eq!(index.suggestions("pikchu", 3), ["pichu", "pikachu"]);
+        assert_eq!(index.suggestions("charmandr", 1), ["charmander"]);
+        assert_eq!(index.suggestions("mee", 3), ["mew"]);
+        assert!(index.suggestions("agumon", 3).is_empty());
+        assert!(index.suggestions("charizard", 3).is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let index = NameIndex::new(vec![
+            PokemonName {
+                name: "charizard-mega-x".to_string(),
+                number: None,
+            },
+            PokemonName {
+                name: "charizard".to_string(),
+                number: Some(6),
+            },
+            PokemonName {
+                name: "charmeleon".to_string(),
+                number: Some(5),
+            },
+            PokemonName {
+                name: "charmander".to_string(),
+                number: Some(4),
+            },
+            PokemonName {
+                name: "pikachu".to_string(),
+                number: Some(25),
+            },
+            PokemonName {
+                name: "pichu".to_string(),
+                number: Some(172),
+            },
+        ]);
+        let found = |query: &str, limit: usize| {
+            index
+                .search(query, limit)
+                .into_iter()
+                .map(|known| known.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            found("char", 10),
+            ["charmander", "charmeleon", "charizard", "charizard-mega-x"]
+        );
+        assert_eq!(found("char", 2), ["charmander", "charmeleon"]);
+        assert_eq!(found("chu", 10), ["pikachu", "pichu"]);
+        assert_eq!(found("pi", 10), ["pikachu", "pichu"]);
+        assert_eq!(found("zard", 10), ["charizard", "charizard-mega-x"]);
+        assert!(found("agu", 10).is_empty());
+        assert!(found("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_localized() {
+        let localized_name = |name: &str, language: &str, pokemon: &str| LocalizedName {
+            name: name.to_string(),
+            language: language.to_string(),
+            pokemon: pokemon.to_string(),
+        };
+        let index = make_index(&["charizard", "ditto", "electrode", "tic"])
+            .with_localized_names(vec![
+                localized_name("リザードン", "ja", "charizard"),
+                localized_name("Glurak", "de", "charizard"),
+                localized_name("Charizard", "en", "charizard"),
+                localized_name("Ditto", "de", "ditto"),
+            ])
+            // Loaded later, e.g. after failing to load the first time
+            .with_localized_names(vec![
+                localized_name("Électrode", "fr", "electrode"),
+                localized_name("Tic", "fr", "klink"),
+                localized_name("Glurak", "xx", "ditto"),
+            ]);
+        let resolved = |name: &str| {
+            index.resolve_localized(name).map(|localized_name| {
+                (
+                    localized_name.pokemon.as_str(),
+                    localized_name.language.as_str(),
+                )
+            })
+        };
+        assert_eq!(resolved("リザードン"), Some(("charizard", "ja")));
+        assert_eq!(resolved("glurak"), Some(("charizard", "de")));
+        assert_eq!(resolved("charizard"), None);
+        assert_eq!(resolved("ditto"), None);
+        assert_eq!(resolved("electrode"), None);
+        // The Pokémon with the name wins over the localized name of another one
+        assert_eq!(resolved("tic"), None);
+        assert_eq!(resolved("agumon"), None);
+        assert_eq!(index.localized_names.len(), 3);
+        assert_eq!(index.numbered_names().next(), Some((1, "charizard")));
+    }
+}
@@ -0,0 +1,152 @@
+// Cleaning up Poké API flavor texts before they get cached or translated. The texts come straight
+// from the game cartridges: line breaks and form feeds where the game screen scrolled, soft hyphens
+// where a word was split across lines, names in capitals and the odd typographic character.
+
+pub fn normalize_flavor_text(text: &str) -> String {
+    let text = repair_hyphenation(text).replace('\u{2026}', "...");
+    let text = text.chars().filter_map(normalize_char).collect::<String>();
+    text.split_whitespace()
+        .map(normalize_name_casing)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// A soft hyphen at a line break joins the two halves of the word back together. A regular hyphen
+// at a line break belongs to a hyphenated word, so it stays but the line break goes.
+fn repair_hyphenation(text: &str) -> String {
+    let mut repaired = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{ad}' | '-' if chars.peek().is_some_and(|next| is_line_break(*next)) => {
+                while chars.peek().is_some_and(|next| is_line_break(*next)) {
+                    chars.next();
+                }
+                if c == '-' {
+                    repaired.push('-');
+                }
+            }
+            // Soft hyphens elsewhere are invisible anyway
+            '\u{ad}' => {}
+            c => repaired.push(c),
+        }
+    }
+    repaired
+}
+
+fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{c}' | '\u{b}')
+}
+
+// Whitespace becomes a plain space, control and zero-width characters go away, typographic
+// quotes and dashes become their plain counterparts
+fn normalize_char(c: char) -> Option<char> {
+    match c {
+        c if c.is_whitespace() => Some(' '),
+        '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}' => None,
+        c if c.is_control() => None,
+        '\u{2018}' | '\u{2019}' | '\u{201b}' | '\u{2032}' => Some('\''),
+        '\u{201c}' | '\u{201d}' | '\u{201f}' | '\u{2033}' => Some('"'),
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2212}' => Some('-'),
+        c => Some(c),
+    }
+}
+
+// Parts of names written in capitals that are too short to tell from abbreviations like `HP`
+const CAPITALIZED_NAME_PARTS: &[&str] = &["MR.", "JR."];
+
+// Abbreviations of three or more letters that descriptions use, which stay in capitals
+const ABBREVIATIONS: &[&str] = &["DNA", "ESP", "UFO", "CPU", "LCD", "USB"];
+
+// Games write Pokémon names and the word Pokémon itself in capitals, e.g. `CHARIZARD` and
+// `POKéMON`. Words of three or more capital letters become capitalized, as does every part of a
+// hyphenated name like `HO-OH`. Shorter words like `HP` are most likely abbreviations and stay, as
+// do the known longer ones like `DNA`.
+fn normalize_name_casing(word: &str) -> String {
+    // Possessives like `POKéMON's` don't count
+    let letters = word
+        .split('\'')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<_>>();
+    if ABBREVIATIONS.contains(&letters.iter().collect::<String>().as_str()) {
+        return word.to_string();
+    }
+    let capitals = letters.iter().filter(|c| c.is_uppercase()).count();
+    // The `é` of `POKéMON` is the only lowercase letter games put into capitalized words
+    let is_capitalized = (capitals >= 3 || CAPITALIZED_NAME_PARTS.contains(&word))
+        && letters.iter().all(|c| c.is_uppercase() || *c == 'é');
+    if !is_capitalized {
+        return word.to_string();
+    }
+    let mut seen_letter = false;
+    word.chars()
+        .flat_map(|c| {
+            let first_letter = c.is_alphabetic() && !seen_letter;
+            seen_letter = (seen_letter || c.is_alphabetic()) && c != '-';
+            if first_letter {
+                c.to_uppercase().collect::<Vec<_>>()
+            } else {
+                c.to_lowercase().collect::<Vec<_>>()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_flavor_text() {
+        let cases = [
+            // Line breaks and form feeds
+            (
+                "Spits fire that\nis hot enough to\nmelt boulders.\u{c}Known to cause\nforest fires.",
+                "Spits fire that is hot enough to melt boulders. Known to cause forest fires.",
+            ),
+            ("Windows\r\nline breaks", "Windows line breaks"),
+            // Whitespace collapse
+            ("  Too   many\t\tspaces  ", "Too many spaces"),
+            ("Non\u{a0}breaking", "Non breaking"),
+            // Control and zero-width characters
+            ("Bell\u{7}less", "Bellless"),
+            ("Zero\u{200b}width", "Zerowidth"),
+            ("\u{feff}Byte order mark", "Byte order mark"),
+            // Hyphenation
+            ("It is rest\u{ad}\nless.", "It is restless."),
+            ("Soft\u{ad}hyphen", "Softhyphen"),
+            ("A self-\nimportant POKéMON.", "A self-important Pokémon."),
+            ("Long - dash", "Long - dash"),
+            ("Ends with a hyphen-", "Ends with a hyphen-"),
+            // Name casing
+            (
+                "CHARIZARD flies around the sky.",
+                "Charizard flies around the sky.",
+            ),
+            ("this POKéMON's tail", "this Pokémon's tail"),
+            ("MR. MIME and NIDORAN♀", "Mr. Mime and Nidoran♀"),
+            ("PORYGON-Z and HO-OH", "Porygon-Z and Ho-Oh"),
+            ("MIME JR. copies", "Mime Jr. copies"),
+            ("Restores HP and PP.", "Restores HP and PP."),
+            (
+                "Its DNA is almost the same as MEW's.",
+                "Its DNA is almost the same as Mew's.",
+            ),
+            ("It uses ESP. A UFO?", "It uses ESP. A UFO?"),
+            ("\"DITTO!\"", "\"Ditto!\""),
+            // Typographic characters
+            ("It\u{2019}s \u{201c}hot\u{201d}", "It's \"hot\""),
+            ("Wait\u{2026} what", "Wait... what"),
+            ("Fire\u{2014}water", "Fire-water"),
+            // Nothing to do
+            ("", ""),
+            ("Already clean.", "Already clean."),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(normalize_flavor_text(input), *expected, "input {:?}", input);
+        }
+    }
+}
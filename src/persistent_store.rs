@@ -0,0 +1,321 @@
+// An append-only file keeping cached responses between restarts. The first line of the file is a
+// header with the schema version, every following line is a JSON-encoded cache entry:
+//
+//   {"schema_version":5}
+//   {"map":"descriptions","key":"pikachu@en~longest","value":"...","stored_at":1618000000}
+//
+// Entries are only ever appended, so a key may appear several times and the last one wins. On
+// startup the file is compacted down to the latest value of every key, and again while running
+// whenever as many entries were appended as the last compaction kept, but at least
+// `MIN_APPENDS_BEFORE_COMPACTION`. That way the file stays within about twice its compacted size
+// and compacting costs little per appended entry.
+
+pub const SCHEMA_VERSION: u32 = 5;
+
+const MIN_APPENDS_BEFORE_COMPACTION: usize = 1000;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoreHeader {
+    schema_version: u32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct StoredEntry {
+    pub map: String,
+    pub key: String,
+    pub value: String,
+    // Seconds since the Unix epoch
+    pub stored_at: u64,
+}
+
+impl StoredEntry {
+    // How long ago the entry was stored
+    pub fn age(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(unix_time_now().saturating_sub(self.stored_at))
+    }
+}
+
+pub struct PersistentStore {
+    path: std::path::PathBuf,
+    file: std::sync::Mutex<StoreFile>,
+    min_appends_before_compaction: usize,
+}
+
+struct StoreFile {
+    file: std::fs::File,
+    // Entries kept by the last compaction, and appended since
+    compacted_entries: usize,
+    appended_entries: usize,
+}
+
+impl StoreFile {
+    fn open(path: &std::path::Path, compacted_entries: usize) -> std::io::Result<Self> {
+        Ok(StoreFile {
+            file: std::fs::OpenOptions::new().append(true).open(path)?,
+            compacted_entries,
+            appended_entries: 0,
+        })
+    }
+}
+
+impl PersistentStore {
+    // Opens the store, creating it if needed, and returns it together with all stored entries
+    pub fn open<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> std::io::Result<(PersistentStore, Vec<StoredEntry>)> {
+        let path = path.as_ref();
+        let entries = match std::fs::read_to_string(path) {
+            Ok(content) => match parse_store(&content) {
+                Some(entries) => entries,
+                None => {
+                    let backup_path = path.with_extension("bak");
+                    eprintln!(
+                        "Unsupported or damaged cache file {}, moving it to {}",
+                        path.display(),
+                        backup_path.display()
+                    );
+                    std::fs::rename(path, &backup_path)?;
+                    Vec::new()
+                }
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        let entries = latest_entries(entries);
+        write_compacted(path, &entries)?;
+        let store = PersistentStore {
+            path: path.to_path_buf(),
+            file: std::sync::Mutex::new(StoreFile::open(path, entries.len())?),
+            min_appends_before_compaction: MIN_APPENDS_BEFORE_COMPACTION,
+        };
+        Ok((store, entries))
+    }
+
+    pub fn append(&self, map: &str, key: &str, value: &str) -> std::io::Result<()> {
+        use std::io::Write;
+        let entry = StoredEntry {
+            map: map.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+            stored_at: unix_time_now(),
+        };
+        let line = serde_json::to_string(&entry)? + "\n";
+        let mut store_file = self.file.lock().unwrap();
+        store_file.file.write_all(line.as_bytes())?;
+        store_file.appended_entries += 1;
+        let compaction_threshold = store_file
+            .compacted_entries
+            .max(self.min_appends_before_compaction);
+        if store_file.appended_entries >= compaction_threshold {
+            *store_file = self.compact()?;
+        }
+        Ok(())
+    }
+
+    // Only called while holding the lock on the file, so that nothing gets appended meanwhile
+    fn compact(&self) -> std::io::Result<StoreFile> {
+        let content = std::fs::read_to_string(&self.path)?;
+        let entries = parse_store(&content).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to compact cache file {}", self.path.display()),
+            )
+        })?;
+        let entries = latest_entries(entries);
+        write_compacted(&self.path, &entries)?;
+        StoreFile::open(&self.path, entries.len())
+    }
+}
+
+// Returns `None` if the content isn't a store of a known schema version. Damaged entries, like a
+// line cut short by a crash, are skipped.
+fn parse_store(content: &str) -> Option<Vec<StoredEntry>> {
+    let mut lines = content.lines();
+    let header: StoreHeader = serde_json::from_str(lines.next()?).ok()?;
+    let entries = lines.filter_map(|line| serde_json::from_str::<StoredEntry>(line).ok());
+    match header.schema_version {
+        SCHEMA_VERSION => Some(entries.collect()),
+        // Version 1 kept descriptions as plain text, without their game version and language,
+        // version 2 keyed them by the pokemon name only, without the languages, version 3 kept
+        // them as they come from Poké API, without normalization, and version 4 keyed them without
+        // the description policy. Translations are still good, descriptions will be fetched again.
+        1..=4 => Some(
+            entries
+                .filter(|entry| entry.map != "descriptions")
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+// Keeps only the last entry for every key, preserving the order of appending
+fn latest_entries(entries: Vec<StoredEntry>) -> Vec<StoredEntry> {
+    let mut seen = std::collections::HashSet::new();
+    let mut latest = entries
+        .into_iter()
+        .rev()
+        .filter(|entry| seen.insert((entry.map.clone(), entry.key.clone())))
+        .collect::<Vec<_>>();
+    latest.reverse();
+    latest
+}
+
+// Writes into a temporary file first, so a crash can't leave us with a half-written store
+fn write_compacted(path: &std::path::Path, entries: &[StoredEntry]) -> std::io::Result<()> {
+    let mut content = serde_json::to_string(&StoreHeader {
+        schema_version: SCHEMA_VERSION,
+    })? + "\n";
+    for entry in entries {
+        content += &(serde_json::to_string(entry)? + "\n");
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path)
+}
+
+fn unix_time_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persistent_store() {
+        let dir = crate::fixtures::make_temp_dir("persistent-store");
+        let path = dir.join("cache.jsonl");
+
+        let (store, entries) = PersistentStore::open(&path).unwrap();
+        assert!(entries.is_empty());
+        store.append("descriptions", "pikachu", "yellow").unwrap();
+        store.append("descriptions", "ditto", "purple").unwrap();
+        store
+            .append("descriptions", "pikachu", "still yellow")
+            .unwrap();
+        store
+            .append("shakespearese", "pikachu", "yellow, forsooth")
+            .unwrap();
+        drop(store);
+
+        // A crash in the middle of writing
+        std::fs::write(
+            &path,
+            std::fs::read_to_string(&path).unwrap() + "{\"map\":\"descr",
+        )
+        .unwrap();
+
+        let (_, entries) = PersistentStore::open(&path).unwrap();
+        let entries = entries
+            .iter()
+            .map(|entry| (entry.map.as_str(), entry.key.as_str(), entry.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                ("descriptions", "ditto", "purple"),
+                ("descriptions", "pikachu", "still yellow"),
+                ("shakespearese", "pikachu", "yellow, forsooth"),
+            ]
+        );
+        // Compacted on open
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_store_compaction() {
+        let dir = crate::fixtures::make_temp_dir("persistent-store-compaction");
+        let path = dir.join("cache.jsonl");
+        let line_count = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+        let (mut store, _) = PersistentStore::open(&path).unwrap();
+        store.min_appends_before_compaction = 4;
+        for generation in 0..10 {
+            store
+                .append("descriptions", "pikachu", &format!("yellow {}", generation))
+                .unwrap();
+            // The header, the compacted entry and what came since
+            assert!(line_count() <= 5, "{} lines", line_count());
+        }
+        store.append("descriptions", "ditto", "purple").unwrap();
+        drop(store);
+
+        let (_, entries) = PersistentStore::open(&path).unwrap();
+        let entries = entries
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec![("pikachu", "yellow 9"), ("ditto", "purple")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_store_schema_version_1() {
+        let dir = crate::fixtures::make_temp_dir("persistent-store-version-1");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cache.jsonl");
+        std::fs::write(
+            &path,
+            "{\"schema_version\":1}\n\
+             {\"map\":\"descriptions\",\"key\":\"pikachu\",\"value\":\"yellow\",\"stored_at\":0}\n\
+             {\"map\":\"shakespearese\",\"key\":\"yellow\",\"value\":\"yellow, forsooth\",\"stored_at\":0}\n",
+        )
+        .unwrap();
+
+        let (_, entries) = PersistentStore::open(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].map, "shakespearese");
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("{\"schema_version\":5}\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_store_schema_version_4() {
+        let dir = crate::fixtures::make_temp_dir("persistent-store-version-4");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cache.jsonl");
+        std::fs::write(
+            &path,
+            "{\"schema_version\":4}\n\
+             {\"map\":\"descriptions\",\"key\":\"pikachu@en\",\"value\":\"{}\",\"stored_at\":0}\n\
+             {\"map\":\"shakespearese\",\"key\":\"yellow\",\"value\":\"yellow, forsooth\",\"stored_at\":0}\n",
+        )
+        .unwrap();
+
+        // No key carries the description policy, so no description could ever be found
+        let (_, entries) = PersistentStore::open(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].map, "shakespearese");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_store_unknown_schema_version() {
+        let dir = crate::fixtures::make_temp_dir("persistent-store-schema");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cache.jsonl");
+        let future_store = "{\"schema_version\":1000}\n{\"whatever\":1}\n";
+        std::fs::write(&path, future_store).unwrap();
+
+        let (_, entries) = PersistentStore::open(&path).unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("cache.bak")).unwrap(),
+            future_store
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"schema_version\":5}\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,228 @@
+use crate::http_client::UpstreamResponse;
+use crate::name_index::PokemonName;
+use crate::upstream_client::UpstreamClient;
+use crate::{RequestError, RequestErrorKind, Result, Upstream};
+
+// A source of Pokémon species information, normally Poké API
+#[async_trait::async_trait]
+pub trait PokemonSource: Send + Sync + 'static {
+    // Looks up the species of a pokemon by its name or Pokédex number
+    async fn pokemon_species(&self, pokemon_name: &str) -> Result<PokemonDescriptionResponse>;
+
+    // Names of all pokemon, including alternative forms
+    async fn pokemon_names(&self) -> Result<Vec<PokemonName>>;
+
+    // Names of the species with the Pokédex number in all languages
+    async fn species_names(&self, pokedex_number: u32) -> Result<Vec<PokeApiSpeciesName>>;
+}
+
+// A page of the pokemon list, `next` is the url of the next page if there is one
+#[derive(serde::Deserialize)]
+pub struct PokemonListResponse {
+    pub next: Option<String>,
+    pub results: Vec<PokemonListEntry>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PokemonListEntry {
+    pub name: String,
+    pub url: String,
+}
+
+impl PokemonListEntry {
+    // The url ends with the id of the pokemon, which is its Pokédex number unless it's an
+    // alternative form
+    fn pokedex_number(&self) -> Option<u32> {
+        self.url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .and_then(|id| id.parse::<u32>().ok())
+            .filter(|id| *id < FIRST_ALTERNATIVE_FORM_ID)
+    }
+}
+
+const FIRST_ALTERNATIVE_FORM_ID: u32 = 10001;
+
+// Asking for a page larger than the whole list gets all pokemon in one go
+const POKEMON_LIST_PAGE_SIZE: usize = 100000;
+
+#[derive(serde::Deserialize)]
+struct PokeApiPokemonSpeciesInfo {
+    url: String,
+}
+
+#[derive(serde::Deserialize)]
+struct PokemonResponse {
+    species: PokeApiPokemonSpeciesInfo,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct PokeApiPokemonDescriptionLanguage {
+    pub name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PokeApiPokemonDescriptionVersion {
+    pub name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PokeApiPokemonDescription {
+    pub version: PokeApiPokemonDescriptionVersion,
+    pub flavor_text: String,
+    pub language: PokeApiPokemonDescriptionLanguage,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct PokeApiSpeciesName {
+    pub name: String,
+    pub language: PokeApiPokemonDescriptionLanguage,
+}
+
+#[derive(serde::Deserialize)]
+struct SpeciesNamesResponse {
+    names: Vec<PokeApiSpeciesName>,
+}
+
+// Also cached as JSON, in the same shape as Poké API responds with
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PokemonDescriptionResponse {
+    #[serde(rename = "flavor_text_entries")]
+    pub descriptions: Vec<PokeApiPokemonDescription>,
+}
+
+pub struct PokeApi {
+    url: String,
+    upstream_client: UpstreamClient,
+}
+
+impl PokeApi {
+    pub fn new<S: Into<String>>(url: S, upstream_client: UpstreamClient) -> Self {
+        PokeApi {
+            url: url.into(),
+            upstream_client,
+        }
+    }
+
+    // Results of Poke API queries can depend on presense or absense of trailing slash, so we better
+    // try both options. For example, see
+    // https://pokeapi.co/api/v2/pokemon/klink vs https://pokeapi.co/api/v2/pokemon/klink/
+    // and https://pokeapi.co/api/v2/pokemon/electrode vs https://pokeapi.co/api/v2/pokemon/electrode/
+    async fn query_pokemon_by_name(&self, pokemon_name: &str) -> Result<UpstreamResponse> {
+        let pokemon_request_url = format!("{}/pokemon/{}", &self.url, &pokemon_name);
+        let pokemon_response = self.upstream_client.get(&pokemon_request_url).await?;
+        if !pokemon_response.status.is_success() {
+            let url_with_trailing_slash = pokemon_request_url + "/";
+            let response_with_trailing_slash =
+                self.upstream_client.get(&url_with_trailing_slash).await?;
+            Ok(response_with_trailing_slash)
+        } else {
+            Ok(pokemon_response)
+        }
+    }
+
+    async fn query_species_url(&self, pokemon_name: &str) -> Result<String> {
+        let pokemon_response = self.query_pokemon_by_name(pokemon_name).await?;
+        if !pokemon_response.status.is_success() {
+            return Err(RequestError::from_pokemon_lookup_status(
+                pokemon_response.status,
+                format!(
+                    "Failed to find a pokemon {} by url {}",
+                    &pokemon_name, &pokemon_response.url
+                ),
+            ));
+        }
+
+        let pokemon_response: PokemonResponse = serde_json::from_str(&pokemon_response.body)?;
+        Ok(pokemon_response.species.url)
+    }
+
+    async fn query_species(
+        &self,
+        pokemon_name: &str,
+        species_url: &str,
+    ) -> Result<PokemonDescriptionResponse> {
+        let description_response = self.upstream_client.get(species_url).await?;
+        if !description_response.status.is_success() {
+            return Err(RequestError::from_pokemon_lookup_status(
+                description_response.status,
+                format!(
+                    "Failed to get a species description for the pokemon {} by url {}",
+                    &pokemon_name, species_url
+                ),
+            ));
+        }
+        Ok(serde_json::from_str(&description_response.body)?)
+    }
+}
+
+#[async_trait::async_trait]
+impl PokemonSource for PokeApi {
+    async fn pokemon_species(&self, pokemon_name: &str) -> Result<PokemonDescriptionResponse> {
+        // An empty name would make the url of the pokemon list instead
+        if pokemon_name.is_empty() {
+            return Err(RequestError::new(
+                RequestErrorKind::NotFound,
+                "Pokemon names can't be empty",
+            ));
+        }
+        let species_url = self
+            .query_species_url(pokemon_name)
+            .await
+            .map_err(|err| err.with_upstream(Upstream::PokeApiPokemon))?;
+        self.query_species(pokemon_name, &species_url)
+            .await
+            .map_err(|err| err.with_upstream(Upstream::PokeApiSpecies))
+    }
+
+    async fn pokemon_names(&self) -> Result<Vec<PokemonName>> {
+        let mut names = Vec::new();
+        let mut page_url = Some(format!(
+            "{}/pokemon?limit={}",
+            &self.url, POKEMON_LIST_PAGE_SIZE
+        ));
+        while let Some(url) = page_url {
+            let response = self
+                .upstream_client
+                .get(&url)
+                .await
+                .map_err(|err| err.with_upstream(Upstream::PokeApiPokemon))?;
+            if !response.status.is_success() {
+                return Err(RequestError::from_upstream_status(
+                    response.status,
+                    format!("Failed to list pokemon by url {}", &url),
+                )
+                .with_upstream(Upstream::PokeApiPokemon));
+            }
+            let page: PokemonListResponse = serde_json::from_str(&response.body)
+                .map_err(|err| RequestError::from(err).with_upstream(Upstream::PokeApiPokemon))?;
+            names.extend(page.results.iter().map(|entry| PokemonName {
+                name: entry.name.clone(),
+                number: entry.pokedex_number(),
+            }));
+            // Stop rather than loop forever should the upstream keep sending empty pages
+            page_url = page.next.filter(|_| !page.results.is_empty());
+        }
+        Ok(names)
+    }
+
+    async fn species_names(&self, pokedex_number: u32) -> Result<Vec<PokeApiSpeciesName>> {
+        let species_url = format!("{}/pokemon-species/{}", &self.url, pokedex_number);
+        let response = self
+            .upstream_client
+            .get(&species_url)
+            .await
+            .map_err(|err| err.with_upstream(Upstream::PokeApiSpecies))?;
+        if !response.status.is_success() {
+            return Err(RequestError::from_upstream_status(
+                response.status,
+                format!("Failed to get the species names by url {}", &species_url),
+            )
+            .with_upstream(Upstream::PokeApiSpecies));
+        }
+        let species: SpeciesNamesResponse = serde_json::from_str(&response.body)
+            .map_err(|err| RequestError::from(err).with_upstream(Upstream::PokeApiSpecies))?;
+        Ok(species.names)
+    }
+}
@@ -0,0 +1,82 @@
+// The Pokémon of the day is picked by hashing the UTC day together with an optional seed, so every
+// instance of the service picks the same one for the whole day, also across restarts. Different
+// seeds, e.g. one per client app, get different Pokémon of the day.
+
+use crate::fixtures::fnv1a_hash;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Days since the Unix epoch in UTC
+pub fn utc_day(time: std::time::SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or(0)
+}
+
+// How long until the given day starts at midnight UTC, zero if it already has
+pub fn until_utc_day(day: u64, time: std::time::SystemTime) -> std::time::Duration {
+    let since_epoch = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    std::time::Duration::from_secs(day * SECONDS_PER_DAY).saturating_sub(since_epoch)
+}
+
+// Position of the Pokémon of the day among `count` Pokémon, `None` when there are none
+pub fn daily_position(day: u64, seed: Option<&str>, count: usize) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    let mut input = day.to_le_bytes().to_vec();
+    if let Some(seed) = seed {
+        input.extend_from_slice(seed.as_bytes());
+    }
+    Some((fnv1a_hash(&input) % count as u64) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utc_day() {
+        let time = |seconds| std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds);
+        // 2024-05-01T00:00:00Z and a second before it
+        assert_eq!(utc_day(time(1714521600)), 19844);
+        assert_eq!(utc_day(time(1714521599)), 19843);
+        assert_eq!(
+            until_utc_day(19844, time(1714521599)),
+            std::time::Duration::from_secs(1)
+        );
+        assert_eq!(
+            until_utc_day(19845, time(1714521600)),
+            std::time::Duration::from_secs(SECONDS_PER_DAY)
+        );
+        assert_eq!(
+            until_utc_day(19844, time(1714521601)),
+            std::time::Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_daily_position() {
+        assert_eq!(daily_position(19844, None, 0), None);
+        assert_eq!(daily_position(19844, None, 1), Some(0));
+        assert_eq!(
+            daily_position(19844, Some("seed"), 1025),
+            daily_position(19844, Some("seed"), 1025)
+        );
+        // Over a couple of months, the pick changes from day to day and most Pokémon come up
+        let positions = (19844..19904)
+            .map(|day| daily_position(day, None, 10).unwrap())
+            .collect::<Vec<_>>();
+        assert!(positions.windows(2).any(|pair| pair[0] != pair[1]));
+        let distinct = positions
+            .iter()
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        assert!(distinct >= 8, "only {} distinct picks", distinct);
+        assert!((0..20).any(|day| {
+            daily_position(day, Some("a"), 1025) != daily_position(day, Some("b"), 1025)
+        }));
+    }
+}
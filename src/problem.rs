@@ -0,0 +1,183 @@
+// Error responses in the RFC 7807 `application/problem+json` format. Every problem carries a stable
+// error code clients can rely on and a request id to find the request in the logs. The detailed
+// error description may mention internal urls, so it's only included when explicitly enabled.
+
+use crate::{RequestError, RequestErrorKind, Upstream};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
+    pub code: String,
+    pub upstream: Option<Upstream>,
+    pub request_id: String,
+    // "Did you mean" names when there is no Pokémon with the requested name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_detail: Option<String>,
+}
+
+impl ProblemDetails {
+    pub fn new(
+        err: &RequestError,
+        instance: &str,
+        request_id: &str,
+        expose_internal_details: bool,
+    ) -> Self {
+        let (code, detail) = describe_problem(err);
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: err
+                .status()
+                .canonical_reason()
+                .unwrap_or("Unknown reason")
+                .to_string(),
+            status: err.status().as_u16(),
+            detail: detail.to_string(),
+            instance: instance.to_string(),
+            code: code.to_string(),
+            upstream: err.upstream,
+            request_id: request_id.to_string(),
+            suggestions: err.suggestions.clone(),
+            internal_detail: Some(err.description.clone()).filter(|_| expose_internal_details),
+        }
+    }
+
+    pub fn into_response(self) -> http::Response<String> {
+        let status = http::StatusCode::from_u16(self.status)
+            .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        let request_id = self.request_id.clone();
+        // Serializing a struct of strings and numbers can't fail
+        let body = serde_json::to_string_pretty(&self).unwrap_or_default();
+        http::response::Builder::new()
+            .status(status)
+            .header("Content-Type", "application/problem+json; charset=UTF-8")
+            .header("X-Request-Id", request_id)
+            .body(body)
+            .unwrap()
+    }
+}
+
+// Stable error code and a message that is safe to show to anyone
+fn describe_problem(err: &RequestError) -> (&'static str, &'static str) {
+    match err.kind {
+        RequestErrorKind::NotFound => ("pokemon-not-found", "There is no Pokémon with this name"),
+        RequestErrorKind::NoDescriptionInLanguage => (
+            "no-description-in-language",
+            "There is no description of this Pokémon in the requested languages",
+        ),
+        RequestErrorKind::NoDescriptionInVersion => (
+            "no-description-in-version",
+            "There is no description of this Pokémon from the requested game version",
+        ),
+        RequestErrorKind::InvalidBatch => (
+            "invalid-batch",
+            "A batch has to name at least one and at most the allowed number of Pokémon",
+        ),
+        RequestErrorKind::InvalidSuggestQuery => (
+            "invalid-suggest-query",
+            "Suggestions need a `q` parameter and a numeric `limit`, if any",
+        ),
+        RequestErrorKind::RateLimited => (
+            "translator-rate-limited",
+            "The Shakespeare translator quota is used up, please try again later",
+        ),
+        RequestErrorKind::UpstreamTimeout => (
+            "upstream-timeout",
+            "An upstream service didn't respond in time",
+        ),
+        RequestErrorKind::UpstreamConnect => (
+            "upstream-unavailable",
+            "Couldn't connect to an upstream service",
+        ),
+        RequestErrorKind::UpstreamRequest | RequestErrorKind::UpstreamStatus(_) => (
+            "upstream-error",
+            "An upstream service failed to respond properly",
+        ),
+        RequestErrorKind::CircuitOpen => (
+            "upstream-circuit-open",
+            "An upstream service is failing, please try again later",
+        ),
+        RequestErrorKind::MalformedUpstreamJson => (
+            "upstream-malformed-response",
+            "An upstream service responded with something we don't understand",
+        ),
+        RequestErrorKind::Internal => ("internal-error", "Something went wrong on our side"),
+    }
+}
+
+// Unique within the process and unlikely to repeat across restarts
+pub fn make_request_id() -> String {
+    static REQUEST_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let now_millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    let request_number = REQUEST_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("{:x}-{:06x}", now_millis, request_number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_details() {
+        let err = RequestError::from_pokemon_lookup_status(
+            http::StatusCode::NOT_FOUND,
+            "Failed to find a pokemon agumon by url http://localhost/api/v2/pokemon/agumon/",
+        )
+        .with_upstream(Upstream::PokeApiPokemon);
+        let problem = ProblemDetails::new(&err, "/pokemon/agumon", "42", false);
+        assert_eq!(problem.code, "pokemon-not-found");
+        assert_eq!(problem.title, "Not Found");
+        assert_eq!(problem.upstream, Some(Upstream::PokeApiPokemon));
+        assert!(problem.internal_detail.is_none());
+        assert!(problem.suggestions.is_empty());
+
+        let response = problem.into_response();
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()["X-Request-Id"], "42");
+        let body: serde_json::Value = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(body["upstream"], "pokeapi-pokemon");
+        assert!(!response.body().contains("localhost"));
+
+        assert!(!response.body().contains("suggestions"));
+
+        let problem = ProblemDetails::new(&err, "/pokemon/agumon", "42", true);
+        assert!(problem.internal_detail.unwrap().contains("localhost"));
+
+        let err = err.with_suggestions(vec!["pikachu".to_string()]);
+        let body: serde_json::Value = serde_json::from_str(
+            ProblemDetails::new(&err, "/pokemon/agumon", "42", false)
+                .into_response()
+                .body(),
+        )
+        .unwrap();
+        assert_eq!(body["suggestions"], serde_json::json!(["pikachu"]));
+
+        let problem = ProblemDetails::new(
+            &RequestError::from_upstream_status(http::StatusCode::BAD_GATEWAY, "Bad gateway")
+                .with_upstream(Upstream::Translator),
+            "/pokemon/pikachu",
+            "43",
+            false,
+        );
+        assert_eq!(problem.code, "upstream-error");
+        let problem = ProblemDetails::new(
+            &RequestError::new_internal("Oops"),
+            "/pokemon/pikachu",
+            "44",
+            false,
+        );
+        assert_eq!(problem.code, "internal-error");
+        assert!(problem.upstream.is_none());
+
+        assert_ne!(make_request_id(), make_request_id());
+    }
+}
@@ -0,0 +1,266 @@
+// Errors of serving a request. The kind of an error tells what went wrong and determines the
+// status of the response, while the description and the source are there for the logs.
+
+// The upstream service a request failed at
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Upstream {
+    // Looking up a pokemon by its name in Poké API
+    #[serde(rename = "pokeapi-pokemon")]
+    PokeApiPokemon,
+    // Fetching the species description of a pokemon from Poké API
+    #[serde(rename = "pokeapi-species")]
+    PokeApiSpecies,
+    Translator,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestErrorKind {
+    // The upstream service didn't respond in time
+    UpstreamTimeout,
+    // Couldn't connect to the upstream service
+    UpstreamConnect,
+    // The request to the upstream service failed after connecting, e.g. the connection dropped
+    UpstreamRequest,
+    // The upstream service responded with an unexpected 4xx or 5xx status
+    UpstreamStatus(http::StatusCode),
+    // The upstream response isn't the JSON we expect
+    MalformedUpstreamJson,
+    // The upstream service kept failing recently, so we didn't even try
+    CircuitOpen,
+    // The translator quota is used up
+    RateLimited,
+    // There is no such pokemon
+    NotFound,
+    // The pokemon has no description in any of the requested languages
+    NoDescriptionInLanguage,
+    // The pokemon has no description of the requested game version in any of the languages
+    NoDescriptionInVersion,
+    // A batch request names no pokemon or more than allowed
+    InvalidBatch,
+    // A suggest request lacks `q` or has a `limit` that isn't a number
+    InvalidSuggestQuery,
+    Internal,
+}
+
+// Errors are shared between requests coalesced into one, hence the source is reference counted
+pub type ErrorSource = std::sync::Arc<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone)]
+pub struct RequestError {
+    pub kind: RequestErrorKind,
+    pub description: String,
+    // `None` if the error happened on our side
+    pub upstream: Option<Upstream>,
+    // Names of pokemon the client may have meant when there is no pokemon with the given name
+    pub suggestions: Vec<String>,
+    source: Option<ErrorSource>,
+}
+
+impl RequestError {
+    pub fn new<S: Into<String>>(kind: RequestErrorKind, description: S) -> RequestError {
+        RequestError {
+            kind,
+            description: description.into(),
+            upstream: None,
+            suggestions: Vec::new(),
+            source: None,
+        }
+    }
+
+    pub fn new_internal<S: Into<String>>(description: S) -> RequestError {
+        RequestError::new(RequestErrorKind::Internal, description)
+    }
+
+    // For an upstream response with an error status. Too many requests is meaningful to our
+    // clients and keeps its status, anything else is a failure of the upstream.
+    pub fn from_upstream_status<S: Into<String>>(
+        status: http::StatusCode,
+        description: S,
+    ) -> RequestError {
+        let kind = match status {
+            http::StatusCode::TOO_MANY_REQUESTS => RequestErrorKind::RateLimited,
+            status => RequestErrorKind::UpstreamStatus(status),
+        };
+        RequestError::new(kind, description)
+    }
+
+    // For a Poké API response to looking up a Pokémon by name, where not found means there is no
+    // such Pokémon rather than a failure of the upstream
+    pub fn from_pokemon_lookup_status<S: Into<String>>(
+        status: http::StatusCode,
+        description: S,
+    ) -> RequestError {
+        match status {
+            http::StatusCode::NOT_FOUND => {
+                RequestError::new(RequestErrorKind::NotFound, description)
+            }
+            status => RequestError::from_upstream_status(status, description),
+        }
+    }
+
+    // Wraps an error that can't be attributed to an upstream service
+    pub fn from_internal<E: std::error::Error + Send + Sync + 'static>(error: E) -> RequestError {
+        RequestError::new_internal(error.to_string()).with_source(error)
+    }
+
+    pub fn with_upstream(self, upstream: Upstream) -> RequestError {
+        RequestError {
+            upstream: Some(upstream),
+            ..self
+        }
+    }
+
+    pub fn with_suggestions(self, suggestions: Vec<String>) -> RequestError {
+        RequestError {
+            suggestions,
+            ..self
+        }
+    }
+
+    pub fn with_source<E: std::error::Error + Send + Sync + 'static>(
+        self,
+        source: E,
+    ) -> RequestError {
+        RequestError {
+            source: Some(std::sync::Arc::new(source)),
+            ..self
+        }
+    }
+
+    pub fn status(&self) -> http::StatusCode {
+        match self.kind {
+            RequestErrorKind::UpstreamTimeout => http::StatusCode::GATEWAY_TIMEOUT,
+            RequestErrorKind::UpstreamConnect
+            | RequestErrorKind::UpstreamRequest
+            | RequestErrorKind::UpstreamStatus(_)
+            | RequestErrorKind::MalformedUpstreamJson => http::StatusCode::BAD_GATEWAY,
+            RequestErrorKind::CircuitOpen => http::StatusCode::SERVICE_UNAVAILABLE,
+            RequestErrorKind::RateLimited => http::StatusCode::TOO_MANY_REQUESTS,
+            RequestErrorKind::NotFound | RequestErrorKind::NoDescriptionInVersion => {
+                http::StatusCode::NOT_FOUND
+            }
+            RequestErrorKind::NoDescriptionInLanguage => http::StatusCode::UNPROCESSABLE_ENTITY,
+            RequestErrorKind::InvalidBatch | RequestErrorKind::InvalidSuggestQuery => {
+                http::StatusCode::BAD_REQUEST
+            }
+            RequestErrorKind::Internal => http::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        self.kind == RequestErrorKind::RateLimited
+    }
+
+    // Whether the same request may well succeed if retried
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self.kind,
+            RequestErrorKind::UpstreamTimeout
+                | RequestErrorKind::UpstreamConnect
+                | RequestErrorKind::UpstreamRequest
+        )
+    }
+}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(error: reqwest::Error) -> Self {
+        let kind = if error.is_timeout() {
+            RequestErrorKind::UpstreamTimeout
+        } else if error.is_connect() {
+            RequestErrorKind::UpstreamConnect
+        } else if error.is_decode() {
+            RequestErrorKind::MalformedUpstreamJson
+        } else if error.is_builder() {
+            RequestErrorKind::Internal
+        } else {
+            RequestErrorKind::UpstreamRequest
+        };
+        RequestError::new(kind, error.to_string()).with_source(error)
+    }
+}
+
+// The only JSON we parse is upstream responses. Serializing our own responses goes through
+// `from_internal` instead.
+impl From<serde_json::Error> for RequestError {
+    fn from(error: serde_json::Error) -> Self {
+        RequestError::new(RequestErrorKind::MalformedUpstreamJson, error.to_string())
+            .with_source(error)
+    }
+}
+
+impl From<url::ParseError> for RequestError {
+    fn from(error: url::ParseError) -> Self {
+        RequestError::from_internal(error)
+    }
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{:?}", self.kind)?;
+        if let Some(upstream) = self.upstream {
+            write!(formatter, " at {:?}", upstream)?;
+        }
+        write!(formatter, ": {}", &self.description)
+    }
+}
+
+impl std::error::Error for RequestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| source.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_error_status() {
+        let statuses = [
+            (http::StatusCode::NOT_FOUND, http::StatusCode::BAD_GATEWAY),
+            (
+                http::StatusCode::TOO_MANY_REQUESTS,
+                http::StatusCode::TOO_MANY_REQUESTS,
+            ),
+            (http::StatusCode::BAD_REQUEST, http::StatusCode::BAD_GATEWAY),
+            (
+                http::StatusCode::SERVICE_UNAVAILABLE,
+                http::StatusCode::BAD_GATEWAY,
+            ),
+        ];
+        for (upstream_status, status) in statuses.iter() {
+            let err = RequestError::from_upstream_status(*upstream_status, "Oops");
+            assert_eq!(err.status(), *status, "upstream status {}", upstream_status);
+        }
+        // Only a Pokémon Poké API doesn't know is not found for our clients too
+        let err = RequestError::from_pokemon_lookup_status(http::StatusCode::NOT_FOUND, "Oops");
+        assert_eq!(err.kind, RequestErrorKind::NotFound);
+        assert_eq!(err.status(), http::StatusCode::NOT_FOUND);
+        let err = RequestError::from_pokemon_lookup_status(http::StatusCode::BAD_REQUEST, "Oops");
+        assert_eq!(err.status(), http::StatusCode::BAD_GATEWAY);
+        assert_eq!(
+            RequestError::new(RequestErrorKind::UpstreamTimeout, "Slow").status(),
+            http::StatusCode::GATEWAY_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn test_request_error_source_chain() {
+        use std::error::Error;
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let err = RequestError::from(json_error).with_upstream(Upstream::Translator);
+        assert_eq!(err.kind, RequestErrorKind::MalformedUpstreamJson);
+        assert_eq!(err.status(), http::StatusCode::BAD_GATEWAY);
+        assert!(err.source().unwrap().is::<serde_json::Error>());
+        assert!(err
+            .to_string()
+            .starts_with("MalformedUpstreamJson at Translator: "));
+
+        // Sources survive cloning for coalesced requests
+        assert!(err.clone().source().is_some());
+        assert!(RequestError::new_internal("Oops").source().is_none());
+    }
+}
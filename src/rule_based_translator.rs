@@ -0,0 +1,368 @@
+// A deterministic offline translator into Shakespearese. It's nowhere near as good as Shakespeare
+// translator API, but it's always available and has no request quota.
+//
+// The translation is done word by word with a few contextual rules:
+// - two-word phrases like "you are" -> "thou art" are substituted first,
+// - then single words from the dictionary, e.g. "has" -> "hath", "your" -> "thy",
+// - a third-person verb following a subject like "it" or "CHARIZARD" is conjugated, e.g.
+//   "builds" -> "buildeth",
+// - "you" becomes "thou" at the start of a sentence and "thee" otherwise.
+
+use crate::config::TranslatorEngine;
+use crate::translator::Translator;
+use crate::Result;
+
+const PHRASES: &[(&str, &str, &str)] = &[
+    ("you", "are", "thou art"),
+    ("you", "were", "thou wert"),
+    ("you", "have", "thou hast"),
+    ("you", "do", "thou dost"),
+    ("you", "will", "thou wilt"),
+    ("you", "shall", "thou shalt"),
+    ("you", "can", "thou canst"),
+    ("are", "you", "art thou"),
+    ("were", "you", "wert thou"),
+    ("have", "you", "hast thou"),
+    ("do", "you", "dost thou"),
+    ("will", "you", "wilt thou"),
+    ("can", "you", "canst thou"),
+    ("it", "is", "'tis"),
+    ("it", "was", "'twas"),
+];
+
+const WORDS: &[(&str, &str)] = &[
+    ("your", "thy"),
+    ("yours", "thine"),
+    ("yourself", "thyself"),
+    ("has", "hath"),
+    ("does", "doth"),
+    ("doesn't", "doth not"),
+    ("don't", "do not"),
+    ("isn't", "is not"),
+    ("can't", "cannot"),
+    ("won't", "shall not"),
+    ("over", "o'er"),
+    ("never", "ne'er"),
+    ("ever", "e'er"),
+    ("even", "e'en"),
+    ("before", "ere"),
+    ("between", "betwixt"),
+    ("among", "amongst"),
+    ("often", "oft"),
+    ("perhaps", "perchance"),
+    ("maybe", "perchance"),
+    ("enough", "enow"),
+    ("nothing", "nought"),
+    ("yes", "aye"),
+    ("why", "wherefore"),
+    ("quickly", "apace"),
+    ("hello", "well met"),
+    ("goodbye", "farewell"),
+    ("friend", "sirrah"),
+    ("boy", "lad"),
+    ("girl", "lass"),
+    ("cat", "gib"),
+    ("killed", "did kill"),
+    ("those", "yond"),
+];
+
+// Words after which a verb ending in "s" is in the third person singular
+const SUBJECTS: &[&str] = &["he", "she", "it", "this", "that", "who", "which", "one"];
+
+// Capitalized words starting a sentence that aren't its subject, unlike names, e.g. "The flames"
+const CAPITALIZED_NON_SUBJECTS: &[&str] = &[
+    "a", "all", "an", "and", "as", "at", "but", "by", "each", "every", "for", "from", "however",
+    "if", "in", "its", "many", "my", "no", "of", "on", "once", "or", "our", "so", "some", "the",
+    "their", "these", "to", "when", "while", "with", "your",
+];
+
+// Adverbs allowed between a subject and its verb, e.g. "it often flies"
+const ADVERBS: &[&str] = &[
+    "also",
+    "always",
+    "often",
+    "oft",
+    "never",
+    "ne'er",
+    "sometimes",
+    "then",
+];
+
+// Words ending in "s" which are never verbs
+const NOT_VERBS: &[&str] = &[
+    "is", "was", "has", "does", "its", "this", "his", "hers", "as", "us",
+];
+
+#[derive(Default)]
+pub struct RuleBasedTranslator;
+
+impl RuleBasedTranslator {
+    pub fn translate(&self, input: &str) -> String {
+        let tokens = tokenize(input);
+        let mut output = String::with_capacity(input.len() + input.len() / 4);
+        // Original words preceding the current one, most recent last
+        let mut previous_words: Vec<&str> = Vec::new();
+        let mut sentence_start = true;
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = tokens[index];
+            if !is_word(token) {
+                if token.contains(['.', '!', '?', ';', ':']) {
+                    sentence_start = true;
+                }
+                output.push_str(token);
+                index += 1;
+                continue;
+            }
+
+            let word = token.to_lowercase();
+            if let Some(replacement) = match_phrase(&tokens, index, &word) {
+                output.push_str(&match_case(token, replacement));
+                previous_words.push(token);
+                previous_words.push(tokens[index + 2]);
+                index += 3;
+            } else {
+                let replacement = translate_word(token, &word, &previous_words, sentence_start);
+                output.push_str(&replacement);
+                previous_words.push(token);
+                index += 1;
+            }
+            sentence_start = false;
+        }
+        output
+    }
+}
+
+#[async_trait::async_trait]
+impl Translator for RuleBasedTranslator {
+    async fn shakespearise(&self, input: &str) -> Result<String> {
+        Ok(self.translate(input))
+    }
+
+    fn engine(&self) -> TranslatorEngine {
+        TranslatorEngine::RuleBased
+    }
+}
+
+fn is_word(token: &str) -> bool {
+    token.starts_with(char::is_alphabetic)
+}
+
+// Splits the input into alternating runs of words and everything else. Apostrophes inside words,
+// like in "doesn't", are kept as part of the word.
+fn tokenize(input: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut token_start = 0;
+    let mut in_word = false;
+    let mut chars = input.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let continues_word = c.is_alphabetic()
+            || (in_word
+                && (c == '\'' || c == '’')
+                && chars
+                    .peek()
+                    .map(|(_, next)| next.is_alphabetic())
+                    .unwrap_or(false));
+        if position == 0 {
+            in_word = continues_word;
+        } else if continues_word != in_word {
+            tokens.push(&input[token_start..position]);
+            token_start = position;
+            in_word = continues_word;
+        }
+    }
+    if token_start < input.len() {
+        tokens.push(&input[token_start..]);
+    }
+    tokens
+}
+
+fn match_phrase(tokens: &[&str], index: usize, word: &str) -> Option<&'static str> {
+    let separator = tokens.get(index + 1)?;
+    let next_word = tokens.get(index + 2)?.to_lowercase();
+    if !separator.chars().all(char::is_whitespace) {
+        return None;
+    }
+    PHRASES
+        .iter()
+        .find(|(first, second, _)| *first == word && *second == next_word)
+        .map(|(_, _, replacement)| *replacement)
+}
+
+fn translate_word(
+    token: &str,
+    word: &str,
+    previous_words: &[&str],
+    sentence_start: bool,
+) -> String {
+    if word == "you" {
+        return match_case(token, if sentence_start { "thou" } else { "thee" });
+    }
+    if let Some((_, replacement)) = WORDS.iter().find(|(original, _)| *original == word) {
+        return match_case(token, replacement);
+    }
+    if follows_subject(previous_words) {
+        if let Some(conjugated) = conjugate(word) {
+            return match_case(token, &conjugated);
+        }
+    }
+    token.to_string()
+}
+
+// Subjects are either pronouns or pokemon names, which Poké API spells in all caps, like CHARIZARD,
+// and which are capitalized, like Charizard, once normalized
+fn follows_subject(previous_words: &[&str]) -> bool {
+    let is_subject = |word: &str| {
+        let lowercase = word.to_lowercase();
+        let mut chars = word.chars();
+        let is_capitalized = chars.next().map(char::is_uppercase).unwrap_or(false)
+            && chars.clone().count() > 0
+            && !chars.any(char::is_uppercase);
+        SUBJECTS.contains(&lowercase.as_str())
+            || (word.chars().count() > 1 && word.chars().all(char::is_uppercase))
+            || (is_capitalized && !CAPITALIZED_NON_SUBJECTS.contains(&lowercase.as_str()))
+    };
+    match previous_words {
+        [.., subject, adverb] if ADVERBS.contains(&adverb.to_lowercase().as_str()) => {
+            is_subject(subject)
+        }
+        [.., subject] => is_subject(subject),
+        [] => false,
+    }
+}
+
+// "builds" -> "buildeth", "flies" -> "flieth", "watches" -> "watcheth", "makes" -> "maketh"
+fn conjugate(word: &str) -> Option<String> {
+    if word.len() < 3
+        || !word.ends_with('s')
+        || word.ends_with("ss")
+        || NOT_VERBS.contains(&word)
+        || ADVERBS.contains(&word)
+    {
+        return None;
+    }
+    let stem = &word[..word.len() - 1];
+    if stem.ends_with('e') {
+        Some(format!("{}th", stem))
+    } else {
+        Some(format!("{}eth", stem))
+    }
+}
+
+// Applies the letter case of the original word to its replacement
+fn match_case(original: &str, replacement: &str) -> String {
+    let mut original_letters = original.chars().filter(|c| c.is_alphabetic());
+    let first_letter = original_letters.next();
+    let is_capitalized = first_letter.map(char::is_uppercase).unwrap_or(false);
+    let is_all_caps = is_capitalized
+        && original.chars().filter(|c| c.is_alphabetic()).count() > 1
+        && original_letters.all(char::is_uppercase);
+    if is_all_caps {
+        replacement.to_uppercase()
+    } else if is_capitalized {
+        let mut capitalized = String::with_capacity(replacement.len());
+        let mut capitalize_next = true;
+        for c in replacement.chars() {
+            if capitalize_next && c.is_alphabetic() {
+                capitalized.extend(c.to_uppercase());
+                capitalize_next = false;
+            } else {
+                capitalized.push(c);
+            }
+        }
+        capitalized
+    } else {
+        replacement.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize(""), Vec::<&str>::new());
+        assert_eq!(
+            tokenize("It doesn't fly, does it?"),
+            vec!["It", " ", "doesn't", " ", "fly", ", ", "does", " ", "it", "?"]
+        );
+        assert_eq!(tokenize("'tis Flabébé"), vec!["'", "tis", " ", "Flabébé"]);
+    }
+
+    #[test]
+    fn test_conjugate() {
+        let cases = [
+            ("builds", Some("buildeth")),
+            ("flies", Some("flieth")),
+            ("goes", Some("goeth")),
+            ("watches", Some("watcheth")),
+            ("makes", Some("maketh")),
+            ("spits", Some("spiteth")),
+            ("is", None),
+            ("has", None),
+            ("grass", None),
+            ("build", None),
+        ];
+        for (word, expected) in cases.iter() {
+            assert_eq!(
+                conjugate(word).as_deref(),
+                *expected,
+                "conjugating {}",
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn test_translate() {
+        let translator = RuleBasedTranslator;
+        let cases = [
+            ("", ""),
+            ("Curiosity killed the cat", "Curiosity did kill the gib"),
+            (
+                "Rust is a language that builds reliable software.",
+                "Rust is a language that buildeth reliable software.",
+            ),
+            (
+                "CHARIZARD flies around the sky. It often spits fire.",
+                "CHARIZARD flieth around the sky. It oft spiteth fire.",
+            ),
+            ("It always flies high.", "It always flieth high."),
+            ("It sometimes spits fire.", "It sometimes spiteth fire."),
+            (
+                "Charizard flies around. The flames burn. Its tail glows.",
+                "Charizard flieth around. The flames burn. Its tail glows.",
+            ),
+            ("You are my friend.", "Thou art my sirrah."),
+            (
+                "I will follow you. You follow me.",
+                "I will follow thee. Thou follow me.",
+            ),
+            (
+                "Are you sure it is your egg?",
+                "Art thou sure 'tis thy egg?",
+            ),
+            (
+                "It is over, YOUR pokemon has fainted!",
+                "'Tis o'er, THY pokemon hath fainted!",
+            ),
+            (
+                "Blastoise has water spouts that protrude from its shell.",
+                "Blastoise hath water spouts that protrude from its shell.",
+            ),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(&translator.translate(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_match_case() {
+        assert_eq!(match_case("you", "thou"), "thou");
+        assert_eq!(match_case("You", "thou"), "Thou");
+        assert_eq!(match_case("YOU", "thou"), "THOU");
+        assert_eq!(match_case("It", "'tis"), "'Tis");
+        assert_eq!(match_case("I", "aye"), "Aye");
+    }
+}
@@ -0,0 +1,155 @@
+// Request coalescing: concurrent calls for the same key share a single execution. The first caller
+// becomes the leader and does the work, the rest wait for the leader's result. If the leader is
+// cancelled midway, one of the waiting callers takes over.
+
+use crate::Result;
+
+type Waiters<T> = Vec<futures::channel::oneshot::Sender<Result<T>>>;
+
+pub struct SingleFlight<T> {
+    in_flight: std::sync::Mutex<std::collections::HashMap<String, Waiters<T>>>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        SingleFlight {
+            in_flight: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone> SingleFlight<T> {
+    pub async fn run<F, Future>(&self, key: &str, obtain_value: F) -> Result<T>
+    where
+        F: Fn() -> Future,
+        Future: futures::future::Future<Output = Result<T>>,
+    {
+        loop {
+            let leader_result = {
+                let mut in_flight = self.in_flight.lock().unwrap();
+                match in_flight.get_mut(key) {
+                    Some(waiters) => {
+                        let (sender, receiver) = futures::channel::oneshot::channel();
+                        waiters.push(sender);
+                        Err(receiver)
+                    }
+                    None => {
+                        in_flight.insert(key.to_string(), Vec::new());
+                        Ok(LeaderGuard {
+                            flight: self,
+                            key,
+                            finished: false,
+                        })
+                    }
+                }
+            };
+            match leader_result {
+                Ok(guard) => {
+                    let result = obtain_value().await;
+                    for waiter in guard.finish() {
+                        let _ = waiter.send(result.clone());
+                    }
+                    return result;
+                }
+                Err(receiver) => {
+                    if let Ok(result) = receiver.await {
+                        return result;
+                    }
+                    // The leader was cancelled, so let's try to become the leader ourselves
+                }
+            }
+        }
+    }
+}
+
+// Unregisters the in-flight call when the leader is done or cancelled. Dropping the waiters'
+// senders wakes them up.
+struct LeaderGuard<'flight, 'key, T> {
+    flight: &'flight SingleFlight<T>,
+    key: &'key str,
+    // Once finished, the key may already belong to the next leader
+    finished: bool,
+}
+
+impl<T> LeaderGuard<'_, '_, T> {
+    fn finish(mut self) -> Waiters<T> {
+        self.finished = true;
+        self.flight
+            .in_flight
+            .lock()
+            .unwrap()
+            .remove(self.key)
+            .unwrap_or_default()
+    }
+}
+
+impl<T> Drop for LeaderGuard<'_, '_, T> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.flight.in_flight.lock().unwrap().remove(self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_single_flight_cancelled_leader() {
+        let single_flight = SingleFlight::<String>::default();
+        let call_count = std::sync::atomic::AtomicUsize::new(0);
+        let obtain_value = || async {
+            let call = call_count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            Ok(format!("call {}", call))
+        };
+
+        // The leader gives up after 10ms, the follower takes over
+        let leader = tokio::time::timeout(
+            std::time::Duration::from_millis(10),
+            single_flight.run("pikachu", obtain_value),
+        );
+        let follower = single_flight.run("pikachu", obtain_value);
+        let (leader_result, follower_result) = futures::future::join(leader, follower).await;
+        assert!(leader_result.is_err());
+        assert_eq!(follower_result.unwrap(), "call 1");
+        assert!(single_flight.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_finished_leader_keeps_next_leader() {
+        let single_flight = SingleFlight::<String>::default();
+        let register = || {
+            single_flight
+                .in_flight
+                .lock()
+                .unwrap()
+                .insert("pikachu".to_string(), Vec::new());
+        };
+        register();
+        let mut guard = LeaderGuard {
+            flight: &single_flight,
+            key: "pikachu",
+            finished: false,
+        };
+        single_flight.in_flight.lock().unwrap().remove("pikachu");
+        guard.finished = true;
+        // The next leader registers after the first one finished but before its guard is dropped
+        register();
+        drop(guard);
+        assert!(single_flight
+            .in_flight
+            .lock()
+            .unwrap()
+            .contains_key("pikachu"));
+
+        // A cancelled leader unregisters
+        drop(LeaderGuard {
+            flight: &single_flight,
+            key: "pikachu",
+            finished: false,
+        });
+        assert!(single_flight.in_flight.lock().unwrap().is_empty());
+    }
+}
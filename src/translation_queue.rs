@@ -0,0 +1,76 @@
+// Descriptions that couldn't be translated because of the translator quota. A background worker
+// translates them once the quota frees up, so the following requests get served in Shakespearese.
+
+pub struct TranslationQueue {
+    capacity: usize,
+    pending: std::sync::Mutex<std::collections::VecDeque<String>>,
+}
+
+impl TranslationQueue {
+    pub fn new(capacity: usize) -> Self {
+        TranslationQueue {
+            capacity,
+            pending: std::sync::Mutex::new(std::collections::VecDeque::new()),
+        }
+    }
+
+    // Returns false if the text is already queued or the queue is full
+    pub fn enqueue<S: Into<String>>(&self, text: S) -> bool {
+        let text = text.into();
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() >= self.capacity || pending.contains(&text) {
+            return false;
+        }
+        pending.push_back(text);
+        true
+    }
+
+    pub fn pop(&self) -> Option<String> {
+        self.pending.lock().unwrap().pop_front()
+    }
+
+    // Puts a text back at the head of the queue, for when its translation has to be postponed.
+    // Like `enqueue`, returns false if the text is already queued or the queue filled up meanwhile,
+    // in which case the text is dropped.
+    pub fn requeue(&self, text: String) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() >= self.capacity || pending.contains(&text) {
+            return false;
+        }
+        pending.push_front(text);
+        true
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translation_queue() {
+        let queue = TranslationQueue::new(2);
+        assert!(queue.enqueue("pikachu"));
+        assert!(!queue.enqueue("pikachu"));
+        assert!(queue.enqueue("charizard"));
+        assert!(!queue.enqueue("ditto"));
+        assert_eq!(queue.len(), 2);
+
+        let pikachu = queue.pop().unwrap();
+        assert_eq!(pikachu, "pikachu");
+        assert!(queue.requeue(pikachu));
+        assert_eq!(queue.pop().unwrap(), "pikachu");
+
+        // The queue filled up while pikachu was being translated
+        assert!(queue.enqueue("ditto"));
+        assert!(!queue.requeue("pikachu".to_string()));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop().unwrap(), "charizard");
+        assert_eq!(queue.pop().unwrap(), "ditto");
+        assert!(queue.pop().is_none());
+    }
+}
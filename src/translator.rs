@@ -0,0 +1,80 @@
+use crate::config::TranslatorEngine;
+use crate::upstream_client::UpstreamClient;
+use crate::{RequestError, RequestErrorKind, Result, Upstream};
+
+// A service turning modern English into Shakespearese, normally Shakespeare translator API
+#[async_trait::async_trait]
+pub trait Translator: Send + Sync + 'static {
+    async fn shakespearise(&self, input: &str) -> Result<String>;
+
+    // How many more translations can be requested right now, `None` when there is no quota
+    fn remaining_quota(&self) -> Option<u32> {
+        None
+    }
+
+    fn engine(&self) -> TranslatorEngine;
+}
+
+#[async_trait::async_trait]
+impl<Shakespeare: Translator + ?Sized> Translator for Box<Shakespeare> {
+    async fn shakespearise(&self, input: &str) -> Result<String> {
+        (**self).shakespearise(input).await
+    }
+
+    fn remaining_quota(&self) -> Option<u32> {
+        (**self).remaining_quota()
+    }
+
+    fn engine(&self) -> TranslatorEngine {
+        (**self).engine()
+    }
+}
+
+pub struct FunTranslationsApi {
+    url: String,
+    upstream_client: UpstreamClient,
+}
+
+impl FunTranslationsApi {
+    pub fn new<S: Into<String>>(url: S, upstream_client: UpstreamClient) -> Self {
+        FunTranslationsApi {
+            url: url.into(),
+            upstream_client,
+        }
+    }
+
+    async fn query_translation(&self, input: &str) -> Result<String> {
+        let request_url = reqwest::Url::parse_with_params(
+            &format!("{}/shakespeare.json", &self.url),
+            &[("text", input)],
+        )?;
+        let response = self.upstream_client.get(request_url.as_str()).await?;
+        if !response.status.is_success() {
+            return Err(RequestError::from_upstream_status(
+                response.status,
+                "Failed to query Shakespeare API",
+            ));
+        }
+        let response_json: serde_json::Value = serde_json::from_str(&response.body)?;
+        response_json["contents"]["translated"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(RequestError::new(
+                RequestErrorKind::MalformedUpstreamJson,
+                "Failed to shakespearise the text",
+            ))
+    }
+}
+
+#[async_trait::async_trait]
+impl Translator for FunTranslationsApi {
+    async fn shakespearise(&self, input: &str) -> Result<String> {
+        self.query_translation(input)
+            .await
+            .map_err(|err| err.with_upstream(Upstream::Translator))
+    }
+
+    fn engine(&self) -> TranslatorEngine {
+        TranslatorEngine::FunTranslations
+    }
+}
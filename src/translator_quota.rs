@@ -0,0 +1,227 @@
+// Keeps track of the Shakespeare translator request quota on our side, so we don't waste requests
+// that are bound to be refused with `TOO_MANY_REQUESTS`. Every quota limit, like 5 requests per
+// hour, is modelled as a token bucket that starts full and refills gradually over its period.
+// A request needs a token from every bucket.
+
+use crate::config::{Config, TranslatorEngine};
+use crate::translator::Translator;
+use crate::{RequestError, RequestErrorKind, Result, Upstream};
+
+#[derive(Clone, Copy, Debug)]
+pub struct QuotaLimit {
+    pub requests: u32,
+    pub period: std::time::Duration,
+}
+
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: std::time::Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: QuotaLimit, now: std::time::Instant) -> Self {
+        let capacity = f64::from(limit.requests);
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_second: capacity / limit.period.as_secs_f64(),
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: std::time::Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+}
+
+pub struct TranslatorQuota {
+    buckets: std::sync::Mutex<Vec<TokenBucket>>,
+}
+
+impl TranslatorQuota {
+    pub fn new(config: &Config) -> Self {
+        let hour = std::time::Duration::from_secs(60 * 60);
+        TranslatorQuota::with_limits(&[
+            QuotaLimit {
+                requests: config.translator_hourly_quota,
+                period: hour,
+            },
+            QuotaLimit {
+                requests: config.translator_daily_quota,
+                period: 24 * hour,
+            },
+        ])
+    }
+
+    // Limits with zero requests are ignored
+    pub fn with_limits(limits: &[QuotaLimit]) -> Self {
+        let now = std::time::Instant::now();
+        let buckets = limits
+            .iter()
+            .filter(|limit| limit.requests > 0 && !limit.period.is_zero())
+            .map(|limit| TokenBucket::new(*limit, now))
+            .collect();
+        TranslatorQuota {
+            buckets: std::sync::Mutex::new(buckets),
+        }
+    }
+
+    // Takes a token from every bucket if all of them have one
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_at(std::time::Instant::now())
+    }
+
+    // How many requests can be made right now, `None` if there are no limits
+    pub fn remaining(&self) -> Option<u32> {
+        self.remaining_at(std::time::Instant::now())
+    }
+
+    // Empties all buckets, for when upstream tells us the quota is used up before we think it is
+    pub fn exhaust(&self) {
+        let now = std::time::Instant::now();
+        for bucket in self.buckets.lock().unwrap().iter_mut() {
+            bucket.refill(now);
+            bucket.tokens = 0.0;
+        }
+    }
+
+    fn try_acquire_at(&self, now: std::time::Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        for bucket in buckets.iter_mut() {
+            bucket.refill(now);
+        }
+        if buckets.iter().any(|bucket| bucket.tokens < 1.0) {
+            return false;
+        }
+        for bucket in buckets.iter_mut() {
+            bucket.tokens -= 1.0;
+        }
+        true
+    }
+
+    fn remaining_at(&self, now: std::time::Instant) -> Option<u32> {
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets
+            .iter_mut()
+            .map(|bucket| {
+                bucket.refill(now);
+                bucket.tokens.floor() as u32
+            })
+            .min()
+    }
+}
+
+// Wraps a translator so that it's only called while there is quota left
+pub struct QuotaLimitedTranslator<Shakespeare: Translator> {
+    translator: Shakespeare,
+    quota: TranslatorQuota,
+}
+
+impl<Shakespeare: Translator> QuotaLimitedTranslator<Shakespeare> {
+    pub fn new(translator: Shakespeare, quota: TranslatorQuota) -> Self {
+        QuotaLimitedTranslator { translator, quota }
+    }
+}
+
+#[async_trait::async_trait]
+impl<Shakespeare: Translator> Translator for QuotaLimitedTranslator<Shakespeare> {
+    async fn shakespearise(&self, input: &str) -> Result<String> {
+        if !self.quota.try_acquire() {
+            return Err(RequestError::new(
+                RequestErrorKind::RateLimited,
+                "Shakespeare translator quota is used up",
+            )
+            .with_upstream(Upstream::Translator));
+        }
+        let result = self.translator.shakespearise(input).await;
+        if result.as_ref().is_err_and(RequestError::is_rate_limited) {
+            self.quota.exhaust();
+        }
+        result
+    }
+
+    fn remaining_quota(&self) -> Option<u32> {
+        self.quota.remaining()
+    }
+
+    fn engine(&self) -> TranslatorEngine {
+        self.translator.engine()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes::FakeTranslator;
+
+    #[test]
+    fn test_translator_quota_token_buckets() {
+        let quota = TranslatorQuota::with_limits(&[
+            QuotaLimit {
+                requests: 5,
+                period: std::time::Duration::from_secs(60 * 60),
+            },
+            QuotaLimit {
+                requests: 6,
+                period: std::time::Duration::from_secs(24 * 60 * 60),
+            },
+        ]);
+        let start = std::time::Instant::now();
+        assert_eq!(quota.remaining_at(start), Some(5));
+        for _ in 0..5 {
+            assert!(quota.try_acquire_at(start));
+        }
+        assert!(!quota.try_acquire_at(start));
+        assert_eq!(quota.remaining_at(start), Some(0));
+
+        // One request per 12 minutes trickles back into the hourly bucket
+        let later = start + std::time::Duration::from_secs(12 * 60);
+        assert_eq!(quota.remaining_at(later), Some(1));
+        assert!(quota.try_acquire_at(later));
+
+        // The daily bucket is empty now, even though the hourly one has refilled completely
+        let hour_later = later + std::time::Duration::from_secs(60 * 60);
+        assert_eq!(quota.remaining_at(hour_later), Some(0));
+        assert!(!quota.try_acquire_at(hour_later));
+
+        assert_eq!(TranslatorQuota::with_limits(&[]).remaining(), None);
+    }
+
+    #[tokio::test]
+    async fn test_quota_limited_translator() {
+        let limit = QuotaLimit {
+            requests: 2,
+            period: std::time::Duration::from_secs(60 * 60),
+        };
+        let translator = QuotaLimitedTranslator::new(
+            FakeTranslator::new(),
+            TranslatorQuota::with_limits(&[limit]),
+        );
+        assert_eq!(translator.remaining_quota(), Some(2));
+        assert!(translator.shakespearise("Hello").await.is_ok());
+        assert!(translator.shakespearise("Hello").await.is_ok());
+        let err = translator.shakespearise("Hello").await.unwrap_err();
+        assert_eq!(err.kind, RequestErrorKind::RateLimited);
+        assert_eq!(translator.translator.request_count(), 2);
+
+        // Upstream running out of quota earlier than expected
+        let limit = QuotaLimit {
+            requests: 5,
+            ..limit
+        };
+        let translator = QuotaLimitedTranslator::new(
+            FakeTranslator::with_quota(1),
+            TranslatorQuota::with_limits(&[limit]),
+        );
+        assert!(translator.shakespearise("Hello").await.is_ok());
+        assert!(translator.shakespearise("Hello").await.is_err());
+        assert_eq!(translator.remaining_quota(), Some(0));
+        assert!(translator.shakespearise("Hello").await.is_err());
+        assert_eq!(translator.translator.request_count(), 2);
+    }
+}
@@ -0,0 +1,305 @@
+// Talks to one upstream service on top of `HttpClient`, guarding against the service misbehaving.
+// Every attempt is bounded by a timeout, transient failures (timeouts, connection problems and
+// 5xx responses) are retried with jittered exponential backoff, and a circuit breaker stops
+// sending requests for a while once the service fails repeatedly. While the circuit is open,
+// requests fail fast and the response cache serves stale values where it has them.
+
+use crate::config::Config;
+use crate::http_client::{HttpClient, UpstreamResponse};
+use crate::{RequestError, RequestErrorKind, Result};
+
+#[derive(Clone, Copy, Debug)]
+pub struct UpstreamSettings {
+    // For a single attempt
+    pub timeout: std::time::Duration,
+    pub max_retries: u32,
+    // The delay before the first retry, doubled for every following one
+    pub retry_base_delay: std::time::Duration,
+    // Consecutive failures that open the circuit, 0 to never open it
+    pub failure_threshold: u32,
+    // How long the circuit stays open before letting a trial request through
+    pub open_circuit_duration: std::time::Duration,
+}
+
+impl Default for UpstreamSettings {
+    fn default() -> Self {
+        UpstreamSettings::new(&Config::default(), Config::default().pokeapi_timeout_ms)
+    }
+}
+
+impl UpstreamSettings {
+    pub fn for_pokeapi(config: &Config) -> Self {
+        UpstreamSettings::new(config, config.pokeapi_timeout_ms)
+    }
+
+    // Every attempt spends translator quota, while the quota tracking counts one per translation,
+    // so a failed translation is left for the next request to try again
+    pub fn for_translator(config: &Config) -> Self {
+        UpstreamSettings {
+            max_retries: 0,
+            ..UpstreamSettings::new(config, config.translator_timeout_ms)
+        }
+    }
+
+    fn new(config: &Config, timeout_ms: u64) -> Self {
+        UpstreamSettings {
+            timeout: std::time::Duration::from_millis(timeout_ms),
+            max_retries: config.upstream_max_retries,
+            retry_base_delay: std::time::Duration::from_millis(config.upstream_retry_base_delay_ms),
+            failure_threshold: config.circuit_breaker_threshold,
+            open_circuit_duration: std::time::Duration::from_secs(
+                config.circuit_breaker_open_duration,
+            ),
+        }
+    }
+
+    // Full jitter: a random delay between zero and the exponentially growing maximum
+    fn retry_delay(&self, retry: u32) -> std::time::Duration {
+        use rand::Rng;
+        let max_delay = self
+            .retry_base_delay
+            .saturating_mul(2u32.saturating_pow(retry));
+        max_delay.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+#[derive(Default)]
+struct CircuitBreakerState {
+    consecutive_failures: u32,
+    open_until: Option<std::time::Instant>,
+}
+
+struct CircuitBreaker {
+    failure_threshold: u32,
+    open_circuit_duration: std::time::Duration,
+    state: std::sync::Mutex<CircuitBreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(settings: &UpstreamSettings) -> Self {
+        CircuitBreaker {
+            failure_threshold: settings.failure_threshold,
+            open_circuit_duration: settings.open_circuit_duration,
+            state: std::sync::Mutex::new(CircuitBreakerState::default()),
+        }
+    }
+
+    // Once the circuit has been open long enough, a single trial request is let through and the
+    // circuit stays open for everyone else until the trial succeeds
+    fn allow_request_at(&self, now: std::time::Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            Some(open_until) if now < open_until => false,
+            Some(_) => {
+                state.open_until = Some(now + self.open_circuit_duration);
+                true
+            }
+            None => true,
+        }
+    }
+
+    fn record_at(&self, success: bool, now: std::time::Instant) {
+        let mut state = self.state.lock().unwrap();
+        if success {
+            *state = CircuitBreakerState::default();
+            return;
+        }
+        state.consecutive_failures += 1;
+        if self.failure_threshold > 0 && state.consecutive_failures >= self.failure_threshold {
+            state.open_until = Some(now + self.open_circuit_duration);
+        }
+    }
+}
+
+pub struct UpstreamClient {
+    http_client: std::sync::Arc<HttpClient>,
+    settings: UpstreamSettings,
+    circuit_breaker: CircuitBreaker,
+}
+
+impl Default for UpstreamClient {
+    fn default() -> Self {
+        UpstreamClient::new(Default::default(), UpstreamSettings::default())
+    }
+}
+
+impl UpstreamClient {
+    pub fn new(http_client: std::sync::Arc<HttpClient>, settings: UpstreamSettings) -> Self {
+        UpstreamClient {
+            http_client,
+            settings,
+            circuit_breaker: CircuitBreaker::new(&settings),
+        }
+    }
+
+    // Only for idempotent requests, since they can be retried
+    pub async fn get(&self, url: &str) -> Result<UpstreamResponse> {
+        let mut retry = 0;
+        loop {
+            if !self
+                .circuit_breaker
+                .allow_request_at(std::time::Instant::now())
+            {
+                return Err(RequestError::new(
+                    RequestErrorKind::CircuitOpen,
+                    format!("Not requesting {} since the upstream keeps failing", url),
+                ));
+            }
+            let result = self.get_once(url).await;
+            let transient_failure = match &result {
+                Ok(response) => response.status.is_server_error(),
+                Err(err) => err.is_transient(),
+            };
+            self.circuit_breaker
+                .record_at(!transient_failure, std::time::Instant::now());
+            if !transient_failure || retry >= self.settings.max_retries {
+                return result;
+            }
+            let delay = self.settings.retry_delay(retry);
+            eprintln!("Retrying {} in {} ms", url, delay.as_millis());
+            tokio::time::sleep(delay).await;
+            retry += 1;
+        }
+    }
+
+    async fn get_once(&self, url: &str) -> Result<UpstreamResponse> {
+        match tokio::time::timeout(self.settings.timeout, self.http_client.get(url)).await {
+            Ok(result) => result,
+            Err(_) => Err(RequestError::new(
+                RequestErrorKind::UpstreamTimeout,
+                format!(
+                    "No response from {} in {} ms",
+                    url,
+                    self.settings.timeout.as_millis()
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Responds with the given statuses in turn, after the given delay
+    async fn start_flaky_server(
+        statuses: Vec<u16>,
+        delay: std::time::Duration,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use warp::Filter;
+        let request_count = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = request_count.clone();
+        let route = warp::any().then(move || {
+            let request_number = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let status = statuses[request_number.min(statuses.len() - 1)];
+            async move {
+                tokio::time::sleep(delay).await;
+                warp::reply::with_status("{}", http::StatusCode::from_u16(status).unwrap())
+            }
+        });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (format!("http://{}", address), request_count)
+    }
+
+    fn make_settings(max_retries: u32, failure_threshold: u32) -> UpstreamSettings {
+        UpstreamSettings {
+            timeout: std::time::Duration::from_millis(200),
+            max_retries,
+            retry_base_delay: std::time::Duration::from_millis(10),
+            failure_threshold,
+            open_circuit_duration: std::time::Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upstream_client_retries() {
+        let (url, request_count) =
+            start_flaky_server(vec![503, 502, 200], std::time::Duration::ZERO).await;
+        let client = UpstreamClient::new(Default::default(), make_settings(2, 0));
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.status, http::StatusCode::OK);
+        assert_eq!(request_count.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // Client errors aren't retried
+        let (url, request_count) =
+            start_flaky_server(vec![404, 200], std::time::Duration::ZERO).await;
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.status, http::StatusCode::NOT_FOUND);
+        assert_eq!(request_count.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Giving up eventually
+        let (url, request_count) = start_flaky_server(vec![500], std::time::Duration::ZERO).await;
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.status, http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(request_count.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // Translator requests spend quota on every attempt
+        let (url, request_count) = start_flaky_server(vec![503], std::time::Duration::ZERO).await;
+        let client = UpstreamClient::new(
+            Default::default(),
+            UpstreamSettings::for_translator(&Config::default()),
+        );
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.status, http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(request_count.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_client_timeout() {
+        let (url, request_count) =
+            start_flaky_server(vec![200], std::time::Duration::from_secs(5)).await;
+        let client = UpstreamClient::new(Default::default(), make_settings(1, 0));
+        let err = client.get(&url).await.unwrap_err();
+        assert_eq!(err.kind, RequestErrorKind::UpstreamTimeout);
+        assert_eq!(request_count.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_client_circuit_breaker() {
+        let (url, request_count) =
+            start_flaky_server(vec![503, 503, 503, 200], std::time::Duration::ZERO).await;
+        let client = UpstreamClient::new(Default::default(), make_settings(0, 2));
+        assert!(client.get(&url).await.is_ok());
+        assert!(client.get(&url).await.is_ok());
+        let err = client.get(&url).await.unwrap_err();
+        assert_eq!(err.kind, RequestErrorKind::CircuitOpen);
+        assert_eq!(err.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(request_count.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_circuit_breaker_trial_request() {
+        let breaker = CircuitBreaker::new(&make_settings(0, 2));
+        let start = std::time::Instant::now();
+        breaker.record_at(false, start);
+        assert!(breaker.allow_request_at(start));
+        breaker.record_at(false, start);
+        assert!(!breaker.allow_request_at(start));
+
+        // A single trial request once the circuit has been open long enough
+        let later = start + std::time::Duration::from_secs(61);
+        assert!(breaker.allow_request_at(later));
+        assert!(!breaker.allow_request_at(later));
+
+        // The trial failed, the circuit stays open
+        breaker.record_at(false, later);
+        assert!(!breaker.allow_request_at(later));
+
+        // The next trial succeeded and closes the circuit
+        let even_later = later + std::time::Duration::from_secs(61);
+        assert!(breaker.allow_request_at(even_later));
+        breaker.record_at(true, even_later);
+        assert!(breaker.allow_request_at(even_later));
+        assert!(breaker.allow_request_at(even_later));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let settings = make_settings(5, 0);
+        for retry in 0..5 {
+            let max_delay = std::time::Duration::from_millis(10 * 2u64.pow(retry));
+            assert!(settings.retry_delay(retry) <= max_delay);
+        }
+    }
+}